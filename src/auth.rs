@@ -0,0 +1,126 @@
+use crate::{Error, ErrorKind, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use trackable::error::ErrorKindExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Scope {
+    ReadOnly,
+    ReadWrite,
+}
+
+/// The permission granted to a principal by the access control list of a study.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    Read,
+    Write,
+    Admin,
+}
+
+/// Access control list of a study (principal to permission).
+///
+/// An empty list imposes no restrictions.
+pub type Acl = BTreeMap<String, Permission>;
+
+/// Checks whether `principal` has `permission` on the study protected by `acl`.
+pub fn check_acl(acl: &Acl, principal: Option<&str>, permission: Permission) -> Result<()> {
+    if acl.is_empty() {
+        return Ok(());
+    }
+    let granted = principal.and_then(|p| acl.get(p));
+    track_assert!(
+        granted.is_some_and(|g| *g >= permission),
+        ErrorKind::PermissionDenied,
+        "The principal {:?} does not have the {:?} permission on the study",
+        principal,
+        permission
+    );
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    scope: Scope,
+    principal: Option<String>,
+}
+
+/// Bearer tokens accepted by the HTTP API.
+///
+/// The token file consists of lines of the form `<token> <scope> [<principal>]`
+/// where `<scope>` is either `read-only` or `read-write`.
+/// `<principal>` is the name used in the access control lists of studies.
+/// Empty lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct TokenStore {
+    tokens: HashMap<String, Token>,
+}
+impl TokenStore {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = track!(fs::read_to_string(path.as_ref())
+            .map_err(|e| Error::from(ErrorKind::InvalidInput.cause(e))); path.as_ref())?;
+        track!(content.parse())
+    }
+
+    /// Checks whether the request that has the `Authorization` header value `authorization`
+    /// is allowed to perform an operation that requires `scope`.
+    ///
+    /// Returns the principal associated with the token if it has one.
+    pub fn authorize(&self, authorization: Option<&str>, scope: Scope) -> Result<Option<&str>> {
+        let token = track_assert_some!(
+            authorization.and_then(|v| v.strip_prefix("Bearer ")),
+            ErrorKind::Unauthenticated,
+            "No bearer token"
+        );
+        let granted = track_assert_some!(
+            self.tokens.get(token.trim()),
+            ErrorKind::Unauthenticated,
+            "Unknown token"
+        );
+        track_assert!(
+            granted.scope >= scope,
+            ErrorKind::PermissionDenied,
+            "The token is not allowed to perform this operation: granted={:?}, required={:?}",
+            granted.scope,
+            scope
+        );
+        Ok(granted.principal.as_deref())
+    }
+
+    /// Returns the principal associated with the bearer token in `authorization` (if any).
+    pub fn principal(&self, authorization: Option<&str>) -> Option<&str> {
+        let token = authorization.and_then(|v| v.strip_prefix("Bearer "))?;
+        self.tokens
+            .get(token.trim())
+            .and_then(|t| t.principal.as_deref())
+    }
+}
+impl std::str::FromStr for TokenStore {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut tokens = HashMap::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let token = fields.next().expect("never fails");
+            let scope = match fields.next() {
+                Some("read-only") => Scope::ReadOnly,
+                Some("read-write") => Scope::ReadWrite,
+                scope => track_panic!(
+                    ErrorKind::InvalidInput,
+                    "Invalid scope at line {}: {:?}",
+                    i + 1,
+                    scope
+                ),
+            };
+            let principal = fields.next().map(|p| p.to_owned());
+            tokens.insert(token.to_owned(), Token { scope, principal });
+        }
+        Ok(TokenStore { tokens })
+    }
+}
@@ -0,0 +1,482 @@
+use crate::{Error, ErrorKind, Result};
+use bytecodec::bytes::{BytesEncoder, RemainingBytesDecoder};
+use bytecodec::{ByteCount, Decode, DecodeExt, Encode, EncodeExt, Eos};
+use factory::Factory;
+use hmac::{Hmac, Mac};
+use plumcast::node::{GenerateLocalNodeId, LocalNodeId};
+use sha2::Sha256;
+use std::collections::BTreeSet;
+use std::convert::TryInto;
+use std::fmt;
+use std::fs;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use trackable::error::ErrorKindExt;
+
+/// The length of the tags computed with cluster keys (i.e., HMAC-SHA256).
+pub const TAG_LEN: usize = 32;
+
+/// The length of the binary representation of a [`Seal`].
+pub const SEAL_LEN: usize = TAG_LEN + 16;
+
+/// The maximum difference (in seconds) between the timestamp of an accepted payload and the local clock.
+pub const MAX_PAYLOAD_AGE_SECS: u64 = 300;
+
+/// A secret key shared by all nodes in a cluster.
+///
+/// The RPC and gossip payloads exchanged by plumtuna nodes are sealed with this key (see [`Seal`]),
+/// and payloads with a missing, invalid, stale or replayed seal are dropped.
+/// Payloads are not encrypted.
+///
+/// The membership and tree maintenance messages of plumcast are not sealed.
+/// Instead, the nodes of a cluster with a key use random IDs generated by [`RandomLocalNodeIdGenerator`],
+/// and plumcast drops the messages addressed to unknown IDs,
+/// so a process has to learn the ID of a node from a sealed payload (e.g., the response of the contact RPC) to join it.
+///
+/// Clones of a key share the counter of the payloads that failed verification and the nonces of the accepted payloads.
+#[derive(Clone)]
+pub struct ClusterKey {
+    key: Arc<[u8]>,
+    rejected_messages: Arc<AtomicU64>,
+    seen_nonces: Arc<Mutex<BTreeSet<(u64, u64)>>>,
+}
+impl ClusterKey {
+    pub fn new(key: &[u8]) -> Result<Self> {
+        track_assert!(
+            !key.is_empty(),
+            ErrorKind::InvalidInput,
+            "The cluster key must not be empty"
+        );
+        Ok(ClusterKey {
+            key: Arc::from(key),
+            rejected_messages: Arc::default(),
+            seen_nonces: Arc::default(),
+        })
+    }
+
+    /// Loads a key from the given file (leading and trailing whitespaces are ignored).
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = track!(fs::read_to_string(path.as_ref())
+            .map_err(|e| Error::from(ErrorKind::InvalidInput.cause(e))); path.as_ref())?;
+        track!(Self::new(content.trim().as_bytes()))
+    }
+
+    /// Returns the number of payloads dropped because they failed verification with this key.
+    pub fn rejected_messages(&self) -> u64 {
+        self.rejected_messages.load(Ordering::SeqCst)
+    }
+
+    pub fn sign(&self, data: &[u8]) -> [u8; TAG_LEN] {
+        self.mac(&[], data).finalize().into_bytes().into()
+    }
+
+    /// Seals `data` with the current time and a random nonce.
+    pub fn seal(&self, data: &[u8]) -> Seal {
+        let timestamp = unix_time_secs();
+        let nonce = rand::random();
+        let tag = self
+            .mac(&Seal::header(timestamp, nonce), data)
+            .finalize()
+            .into_bytes()
+            .into();
+        Seal {
+            timestamp,
+            nonce,
+            tag,
+        }
+    }
+
+    /// Verifies `seal` of a received `data` (the payload is counted as rejected if the verification fails).
+    ///
+    /// In addition to the tag, this checks that the timestamp of the seal is within `MAX_PAYLOAD_AGE_SECS`
+    /// from the local clock and that the nonce has not been accepted before.
+    pub fn verify(&self, data: &[u8], seal: Option<&Seal>) -> Result<()> {
+        let result = if let Some(seal) = seal {
+            self.verify_seal(data, seal)
+        } else {
+            Err(track!(Error::from(
+                ErrorKind::PermissionDenied.cause("Missing seal")
+            )))
+        };
+        if let Err(e) = result {
+            let n = self.rejected_messages.fetch_add(1, Ordering::SeqCst) + 1;
+            log::warn!("Drops a payload that failed verification (total: {})", n);
+            return Err(track!(e));
+        }
+        Ok(())
+    }
+
+    /// Verifies only the tag of `seal`.
+    ///
+    /// This is used to re-verify the payloads that have already been accepted by [`ClusterKey::verify`].
+    pub fn verify_tag(&self, data: &[u8], seal: &Seal) -> Result<()> {
+        let header = Seal::header(seal.timestamp, seal.nonce);
+        track_assert!(
+            self.mac(&header, data).verify_slice(&seal.tag).is_ok(),
+            ErrorKind::PermissionDenied,
+            "Invalid tag"
+        );
+        Ok(())
+    }
+
+    fn verify_seal(&self, data: &[u8], seal: &Seal) -> Result<()> {
+        track!(self.verify_tag(data, seal))?;
+
+        let now = unix_time_secs();
+        track_assert!(
+            now.saturating_sub(MAX_PAYLOAD_AGE_SECS) <= seal.timestamp
+                && seal.timestamp <= now.saturating_add(MAX_PAYLOAD_AGE_SECS),
+            ErrorKind::PermissionDenied,
+            "Stale payload: timestamp={}, now={}",
+            seal.timestamp,
+            now
+        );
+
+        let mut seen_nonces = self.seen_nonces.lock().unwrap_or_else(|e| e.into_inner());
+        let oldest = (now.saturating_sub(MAX_PAYLOAD_AGE_SECS), 0);
+        *seen_nonces = seen_nonces.split_off(&oldest);
+        track_assert!(
+            seen_nonces.insert((seal.timestamp, seal.nonce)),
+            ErrorKind::PermissionDenied,
+            "Replayed payload"
+        );
+        Ok(())
+    }
+
+    fn mac(&self, header: &[u8], data: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("never fails");
+        mac.update(header);
+        mac.update(data);
+        mac
+    }
+}
+impl fmt::Debug for ClusterKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ClusterKey(..)")
+    }
+}
+
+/// The authentication data of a payload.
+///
+/// `tag` is the HMAC-SHA256 of the big-endian `timestamp` (UNIX time in seconds) and `nonce` followed by the payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seal {
+    pub timestamp: u64,
+    pub nonce: u64,
+    pub tag: [u8; TAG_LEN],
+}
+impl Seal {
+    /// Returns the binary representation (the tag, the timestamp and the nonce) of this seal.
+    pub fn to_bytes(&self) -> [u8; SEAL_LEN] {
+        let mut bytes = [0; SEAL_LEN];
+        bytes[..TAG_LEN].copy_from_slice(&self.tag);
+        bytes[TAG_LEN..].copy_from_slice(&Self::header(self.timestamp, self.nonce));
+        bytes
+    }
+
+    /// Parses the binary representation made by [`Seal::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != SEAL_LEN {
+            return None;
+        }
+        Some(Seal {
+            timestamp: u64::from_be_bytes(bytes[TAG_LEN..TAG_LEN + 8].try_into().ok()?),
+            nonce: u64::from_be_bytes(bytes[TAG_LEN + 8..].try_into().ok()?),
+            tag: bytes[..TAG_LEN].try_into().ok()?,
+        })
+    }
+
+    fn header(timestamp: u64, nonce: u64) -> [u8; 16] {
+        let mut header = [0; 16];
+        header[..8].copy_from_slice(&timestamp.to_be_bytes());
+        header[8..].copy_from_slice(&nonce.to_be_bytes());
+        header
+    }
+}
+
+/// Generator of random local node IDs.
+///
+/// Unlike the UNIX time based IDs, the generated IDs cannot be guessed by processes that do not have the cluster key.
+#[derive(Debug, Default)]
+pub struct RandomLocalNodeIdGenerator;
+impl GenerateLocalNodeId for RandomLocalNodeIdGenerator {
+    fn generate_local_node_id(&self) -> LocalNodeId {
+        LocalNodeId::new(rand::random())
+    }
+}
+
+fn unix_time_secs() -> u64 {
+    UNIX_EPOCH.elapsed().expect("never fails").as_secs()
+}
+
+/// Encoder that prepends the seal computed with the cluster key (if any) to the bytes encoded by `E`.
+///
+/// If no key is given, the encoded bytes are the same as the ones of `E`.
+#[derive(Debug)]
+pub struct SignedEncoder<E> {
+    inner: E,
+    key: Option<ClusterKey>,
+    bytes: BytesEncoder<Vec<u8>>,
+}
+impl<E: Encode> SignedEncoder<E> {
+    pub fn new(inner: E, key: Option<ClusterKey>) -> Self {
+        SignedEncoder {
+            inner,
+            key,
+            bytes: BytesEncoder::new(),
+        }
+    }
+}
+impl<E: Encode> Encode for SignedEncoder<E> {
+    type Item = E::Item;
+
+    fn encode(&mut self, buf: &mut [u8], eos: Eos) -> bytecodec::Result<usize> {
+        track!(self.bytes.encode(buf, eos))
+    }
+
+    fn start_encoding(&mut self, item: Self::Item) -> bytecodec::Result<()> {
+        let mut bytes = track!(self.inner.encode_into_bytes(item))?;
+        if let Some(key) = &self.key {
+            let mut signed = key.seal(&bytes).to_bytes().to_vec();
+            signed.append(&mut bytes);
+            bytes = signed;
+        }
+        track!(self.bytes.start_encoding(bytes))
+    }
+
+    fn is_idle(&self) -> bool {
+        self.bytes.is_idle()
+    }
+
+    fn requiring_bytes(&self) -> ByteCount {
+        self.bytes.requiring_bytes()
+    }
+}
+impl<E: Encode + Default> Default for SignedEncoder<E> {
+    fn default() -> Self {
+        Self::new(E::default(), None)
+    }
+}
+
+/// Decoder that verifies the seal computed with the cluster key (if any) before decoding the rest by `D`.
+///
+/// Payloads that fail verification result in decoding errors, which make the RPC layer drop them.
+#[derive(Debug)]
+pub struct SignedDecoder<D> {
+    inner: D,
+    key: Option<ClusterKey>,
+    bytes: RemainingBytesDecoder,
+}
+impl<D: Decode> SignedDecoder<D> {
+    pub fn new(inner: D, key: Option<ClusterKey>) -> Self {
+        SignedDecoder {
+            inner,
+            key,
+            bytes: RemainingBytesDecoder::new(),
+        }
+    }
+}
+impl<D: Decode> Decode for SignedDecoder<D> {
+    type Item = D::Item;
+
+    fn decode(&mut self, buf: &[u8], eos: Eos) -> bytecodec::Result<usize> {
+        if self.key.is_some() {
+            track!(self.bytes.decode(buf, eos))
+        } else {
+            track!(self.inner.decode(buf, eos))
+        }
+    }
+
+    fn finish_decoding(&mut self) -> bytecodec::Result<Self::Item> {
+        let key = if let Some(key) = &self.key {
+            key
+        } else {
+            return track!(self.inner.finish_decoding());
+        };
+        let bytes = track!(self.bytes.finish_decoding())?;
+        let (seal, payload) = if bytes.len() >= SEAL_LEN {
+            let (seal, payload) = bytes.split_at(SEAL_LEN);
+            (Seal::from_bytes(seal), payload)
+        } else {
+            (None, &bytes[..])
+        };
+        track!(key
+            .verify(payload, seal.as_ref())
+            .map_err(|e| bytecodec::ErrorKind::InvalidInput.takes_over(e)))?;
+        track!(self.inner.decode_from_bytes(payload))
+    }
+
+    fn requiring_bytes(&self) -> ByteCount {
+        if self.key.is_some() {
+            self.bytes.requiring_bytes()
+        } else {
+            self.inner.requiring_bytes()
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        if self.key.is_some() {
+            self.bytes.is_idle()
+        } else {
+            self.inner.is_idle()
+        }
+    }
+}
+impl<D: Decode + Default> Default for SignedDecoder<D> {
+    fn default() -> Self {
+        Self::new(D::default(), None)
+    }
+}
+
+/// Factory of `SignedEncoder`s used to register RPC handlers.
+#[derive(Debug)]
+pub struct SignedEncoderFactory<E> {
+    key: Option<ClusterKey>,
+    _inner: PhantomData<fn() -> E>,
+}
+impl<E> SignedEncoderFactory<E> {
+    pub fn new(key: Option<ClusterKey>) -> Self {
+        SignedEncoderFactory {
+            key,
+            _inner: PhantomData,
+        }
+    }
+}
+impl<E: Encode + Default> Factory for SignedEncoderFactory<E> {
+    type Item = SignedEncoder<E>;
+
+    fn create(&self) -> Self::Item {
+        SignedEncoder::new(E::default(), self.key.clone())
+    }
+}
+
+/// Factory of `SignedDecoder`s used to register RPC handlers.
+#[derive(Debug)]
+pub struct SignedDecoderFactory<D> {
+    key: Option<ClusterKey>,
+    _inner: PhantomData<fn() -> D>,
+}
+impl<D> SignedDecoderFactory<D> {
+    pub fn new(key: Option<ClusterKey>) -> Self {
+        SignedDecoderFactory {
+            key,
+            _inner: PhantomData,
+        }
+    }
+}
+impl<D: Decode + Default> Factory for SignedDecoderFactory<D> {
+    type Item = SignedDecoder<D>;
+
+    fn create(&self) -> Self::Item {
+        SignedDecoder::new(D::default(), self.key.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytecodec::json_codec::{JsonDecoder, JsonEncoder};
+    use bytecodec::null::{NullDecoder, NullEncoder};
+
+    #[test]
+    fn hmac_sha256_works() {
+        // RFC 4231, test case 2.
+        let key = ClusterKey::new(b"Jefe").unwrap();
+        let tag = key.sign(b"what do ya want for nothing?");
+        assert_eq!(
+            tag.iter().map(|b| format!("{:02x}", b)).collect::<String>(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn signed_codec_works() {
+        let key = ClusterKey::new(b"foo").unwrap();
+        let mut encoder = SignedEncoder::new(JsonEncoder::new(), Some(key.clone()));
+        let bytes = encoder.encode_into_bytes(vec![1, 2, 3]).unwrap();
+        assert_eq!(&bytes[SEAL_LEN..], b"[1,2,3]");
+
+        let mut decoder = SignedDecoder::new(JsonDecoder::<Vec<u8>>::new(), Some(key.clone()));
+        assert_eq!(decoder.decode_from_bytes(&bytes).unwrap(), vec![1, 2, 3]);
+
+        // The same payload is sealed with a different nonce every time.
+        let mut encoder = SignedEncoder::new(JsonEncoder::new(), Some(key.clone()));
+        let another = encoder.encode_into_bytes(vec![1, 2, 3]).unwrap();
+        assert_ne!(bytes, another);
+        let mut decoder = SignedDecoder::new(JsonDecoder::<Vec<u8>>::new(), Some(key.clone()));
+        assert!(decoder.decode_from_bytes(&another).is_ok());
+
+        let mut tampered = bytes.clone();
+        *tampered.last_mut().unwrap() = b'}';
+        let mut decoder = SignedDecoder::new(JsonDecoder::<Vec<u8>>::new(), Some(key.clone()));
+        assert!(decoder.decode_from_bytes(&tampered).is_err());
+
+        let other = ClusterKey::new(b"bar").unwrap();
+        let mut decoder = SignedDecoder::new(JsonDecoder::<Vec<u8>>::new(), Some(other.clone()));
+        assert!(decoder.decode_from_bytes(&bytes).is_err());
+        assert_eq!(key.rejected_messages(), 1);
+        assert_eq!(other.rejected_messages(), 1);
+    }
+
+    #[test]
+    fn replayed_payloads_are_rejected() {
+        let key = ClusterKey::new(b"foo").unwrap();
+        let mut encoder = SignedEncoder::new(NullEncoder, Some(key.clone()));
+        let bytes = encoder.encode_into_bytes(()).unwrap();
+
+        let mut decoder = SignedDecoder::new(NullDecoder, Some(key.clone()));
+        assert!(decoder.decode_from_bytes(&bytes).is_ok());
+        let mut decoder = SignedDecoder::new(NullDecoder, Some(key.clone()));
+        assert!(decoder.decode_from_bytes(&bytes).is_err());
+        assert_eq!(key.rejected_messages(), 1);
+
+        // Clones of the key share the accepted nonces.
+        let mut decoder = SignedDecoder::new(NullDecoder, Some(key.clone()));
+        assert!(decoder.decode_from_bytes(&bytes).is_err());
+        assert_eq!(key.rejected_messages(), 2);
+    }
+
+    #[test]
+    fn stale_payloads_are_rejected() {
+        let key = ClusterKey::new(b"foo").unwrap();
+        let now = unix_time_secs();
+        for &timestamp in &[
+            now - MAX_PAYLOAD_AGE_SECS - 10,
+            now + MAX_PAYLOAD_AGE_SECS + 10,
+        ] {
+            let nonce = 1;
+            let tag = key
+                .mac(&Seal::header(timestamp, nonce), b"[]")
+                .finalize()
+                .into_bytes()
+                .into();
+            let seal = Seal {
+                timestamp,
+                nonce,
+                tag,
+            };
+            assert!(key.verify_tag(b"[]", &seal).is_ok());
+            assert!(key.verify(b"[]", Some(&seal)).is_err());
+        }
+        assert_eq!(key.rejected_messages(), 2);
+
+        let seal = key.seal(b"[]");
+        assert_eq!(Seal::from_bytes(&seal.to_bytes()), Some(seal.clone()));
+        assert!(key.verify(b"[]", Some(&seal)).is_ok());
+    }
+
+    #[test]
+    fn unsigned_codec_keeps_inner_format() {
+        let mut encoder = SignedEncoder::new(NullEncoder, None);
+        assert!(encoder.encode_into_bytes(()).unwrap().is_empty());
+
+        let mut decoder = SignedDecoder::new(NullDecoder, None);
+        assert!(decoder.decode_from_bytes(&[]).is_ok());
+
+        let mut encoder = SignedEncoder::new(JsonEncoder::new(), None);
+        assert_eq!(encoder.encode_into_bytes(vec![1]).unwrap(), b"[1]");
+    }
+}
@@ -1,5 +1,8 @@
+use crate::cluster_key::{ClusterKey, SignedDecoder, SignedEncoder};
 use crate::contact::rpc;
 use crate::Error;
+use bytecodec::json_codec::JsonDecoder;
+use bytecodec::null::NullEncoder;
 use fibers_rpc::client::ClientServiceHandle as RpcClientServiceHandle;
 use fibers_rpc::Call;
 use futures::Future;
@@ -10,17 +13,28 @@ use std::time::Duration;
 #[derive(Debug, Clone)]
 pub struct ContactServiceClient {
     rpc_client_service: RpcClientServiceHandle,
+    cluster_key: Option<ClusterKey>,
 }
 impl ContactServiceClient {
-    pub fn new(rpc_client_service: RpcClientServiceHandle) -> Self {
-        Self { rpc_client_service }
+    pub fn new(
+        rpc_client_service: RpcClientServiceHandle,
+        cluster_key: Option<ClusterKey>,
+    ) -> Self {
+        Self {
+            rpc_client_service,
+            cluster_key,
+        }
     }
 
     pub fn get_contact_node_id(
         &self,
         server_addr: SocketAddr,
     ) -> impl Future<Item = NodeId, Error = Error> {
-        let mut client = rpc::GetContactNodeIdCall::client(&self.rpc_client_service);
+        let mut client = rpc::GetContactNodeIdCall::client_with_codec(
+            &self.rpc_client_service,
+            SignedDecoder::new(JsonDecoder::new(), self.cluster_key.clone()),
+            SignedEncoder::new(NullEncoder, self.cluster_key.clone()),
+        );
         client.options_mut().force_wakeup = true;
         client.options_mut().timeout = Some(Duration::from_millis(100));
         track_err!(client
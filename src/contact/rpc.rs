@@ -1,3 +1,4 @@
+use crate::cluster_key::{SignedDecoder, SignedEncoder};
 use crate::contact::service::ContactServiceHandle;
 use crate::Result;
 use bytecodec::json_codec::{JsonDecoder, JsonEncoder};
@@ -26,13 +27,14 @@ impl Call for GetContactNodeIdCall {
     type Req = ();
     type Res = Result<NodeId>;
 
-    type ReqEncoder = NullEncoder;
-    type ReqDecoder = NullDecoder;
+    type ReqEncoder = SignedEncoder<NullEncoder>;
+    type ReqDecoder = SignedDecoder<NullDecoder>;
 
-    type ResEncoder = JsonEncoder<Self::Res>;
-    type ResDecoder = JsonDecoder<Self::Res>;
+    type ResEncoder = SignedEncoder<JsonEncoder<Self::Res>>;
+    type ResDecoder = SignedDecoder<JsonDecoder<Self::Res>>;
 }
 impl HandleCall<GetContactNodeIdCall> for RpcHandler {
+    #[allow(clippy::redundant_closure)]
     fn handle_call(&self, _: ()) -> Reply<GetContactNodeIdCall> {
         Reply::future(self.service.get_contact_node_id().then(|result| Ok(result)))
     }
@@ -1,4 +1,5 @@
-use crate::contact::rpc::RpcHandler;
+use crate::cluster_key::{ClusterKey, SignedDecoderFactory, SignedEncoderFactory};
+use crate::contact::rpc::{GetContactNodeIdCall, RpcHandler};
 use crate::Error;
 use fibers::sync::{mpsc, oneshot};
 use fibers_rpc::server::ServerBuilder as RpcServerBulider;
@@ -13,7 +14,8 @@ pub struct ContactService {
     contact_node_waitings: Vec<oneshot::Monitored<NodeId, Error>>,
 }
 impl ContactService {
-    pub fn new(rpc: &mut RpcServerBulider) -> Self {
+    /// Makes a new service that answers the contact node ID to the requests authenticated by `cluster_key` (if any).
+    pub fn new(rpc: &mut RpcServerBulider, cluster_key: Option<ClusterKey>) -> Self {
         let (command_tx, command_rx) = mpsc::channel();
         let this = Self {
             contact_node_id: None,
@@ -22,7 +24,11 @@ impl ContactService {
             contact_node_waitings: Vec::new(),
         };
 
-        rpc.add_call_handler(RpcHandler::new(this.handle()));
+        rpc.add_call_handler_with_codec::<GetContactNodeIdCall, _, _, _>(
+            RpcHandler::new(this.handle()),
+            SignedDecoderFactory::new(cluster_key.clone()),
+            SignedEncoderFactory::new(cluster_key),
+        );
         this
     }
 
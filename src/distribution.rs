@@ -1,14 +1,344 @@
+use crate::{ErrorKind, Result};
+use serde_json::Value as JsonValue;
+
+const STEP_EPSILON: f64 = 1e-8;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Distribution {
-    Uniform { low: f64, high: f64 },
-    LogUniform { low: f64, high: f64 },
-    DiscreteUniform { low: f64, high: f64, q: f64 },
-    IntUniform { low: i64, high: i64 },
-    Categorical { choices: Vec<Category> },
+    Uniform {
+        low: f64,
+        high: f64,
+    },
+    LogUniform {
+        low: f64,
+        high: f64,
+    },
+    DiscreteUniform {
+        low: f64,
+        high: f64,
+        q: f64,
+    },
+    IntUniform {
+        low: i64,
+        high: i64,
+    },
+    Categorical {
+        choices: Vec<Category>,
+    },
+    Float {
+        low: f64,
+        high: f64,
+        #[serde(default)]
+        log: bool,
+        #[serde(default)]
+        step: Option<f64>,
+    },
+    Int {
+        low: i64,
+        high: i64,
+        #[serde(default)]
+        log: bool,
+        #[serde(default = "default_int_step")]
+        step: i64,
+    },
+}
+impl Distribution {
+    /// Converts a legacy distribution into the equivalent `Float` or `Int` distribution.
+    ///
+    /// `Categorical`, `Float` and `Int` are returned as they are.
+    pub fn to_modern(&self) -> Distribution {
+        match *self {
+            Distribution::Uniform { low, high } => Distribution::Float {
+                low,
+                high,
+                log: false,
+                step: None,
+            },
+            Distribution::LogUniform { low, high } => Distribution::Float {
+                low,
+                high,
+                log: true,
+                step: None,
+            },
+            Distribution::DiscreteUniform { low, high, q } => Distribution::Float {
+                low,
+                high,
+                log: false,
+                step: Some(q),
+            },
+            Distribution::IntUniform { low, high } => Distribution::Int {
+                low,
+                high,
+                log: false,
+                step: 1,
+            },
+            _ => self.clone(),
+        }
+    }
+
+    /// Converts a `Float` or `Int` distribution into the equivalent legacy distribution.
+    ///
+    /// Returns `None` if there is no legacy equivalent (e.g., log-scaled integer ranges).
+    pub fn to_legacy(&self) -> Option<Distribution> {
+        match *self {
+            Distribution::Float {
+                low,
+                high,
+                log: false,
+                step: None,
+            } => Some(Distribution::Uniform { low, high }),
+            Distribution::Float {
+                low,
+                high,
+                log: true,
+                step: None,
+            } => Some(Distribution::LogUniform { low, high }),
+            Distribution::Float {
+                low,
+                high,
+                log: false,
+                step: Some(q),
+            } => Some(Distribution::DiscreteUniform { low, high, q }),
+            Distribution::Float { .. } => None,
+            Distribution::Int {
+                low,
+                high,
+                log: false,
+                step: 1,
+            } => Some(Distribution::IntUniform { low, high }),
+            Distribution::Int { .. } => None,
+            _ => Some(self.clone()),
+        }
+    }
+
+    pub fn convert(&self, format: DistributionFormat) -> Distribution {
+        match format {
+            DistributionFormat::Modern => self.to_modern(),
+            DistributionFormat::Legacy => self.to_legacy().unwrap_or_else(|| self.clone()),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self.to_modern() {
+            Distribution::Float {
+                low,
+                high,
+                log,
+                step,
+            } => {
+                track_assert!(
+                    low <= high,
+                    ErrorKind::InvalidInput,
+                    "`low` must be less than or equal to `high`: {:?}",
+                    self
+                );
+                if log {
+                    track_assert!(
+                        low > 0.0,
+                        ErrorKind::InvalidInput,
+                        "`low` must be positive for log scale: {:?}",
+                        self
+                    );
+                    track_assert!(
+                        step.is_none(),
+                        ErrorKind::InvalidInput,
+                        "`step` and `log` cannot be used at the same time: {:?}",
+                        self
+                    );
+                }
+                if let Some(step) = step {
+                    track_assert!(
+                        step > 0.0,
+                        ErrorKind::InvalidInput,
+                        "`step` must be positive: {:?}",
+                        self
+                    );
+                }
+            }
+            Distribution::Int {
+                low,
+                high,
+                log,
+                step,
+            } => {
+                track_assert!(
+                    low <= high,
+                    ErrorKind::InvalidInput,
+                    "`low` must be less than or equal to `high`: {:?}",
+                    self
+                );
+                track_assert!(
+                    step > 0,
+                    ErrorKind::InvalidInput,
+                    "`step` must be positive: {:?}",
+                    self
+                );
+                if log {
+                    track_assert!(
+                        low >= 1,
+                        ErrorKind::InvalidInput,
+                        "`low` must be at least 1 for log scale: {:?}",
+                        self
+                    );
+                    track_assert!(
+                        step == 1,
+                        ErrorKind::InvalidInput,
+                        "`step` must be 1 for log scale: {:?}",
+                        self
+                    );
+                }
+            }
+            Distribution::Categorical { choices } => {
+                track_assert!(
+                    !choices.is_empty(),
+                    ErrorKind::InvalidInput,
+                    "`choices` must not be empty"
+                );
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the internal parameter value `value` is included in the distribution.
+    pub fn contains(&self, value: f64) -> bool {
+        if value.is_nan() {
+            return false;
+        }
+        match self.to_modern() {
+            Distribution::Float {
+                low, high, step, ..
+            } => {
+                let on_grid = step.is_none_or(|step| {
+                    let k = (value - low) / step;
+                    (k - k.round()).abs() < STEP_EPSILON
+                });
+                low <= value && value <= high && on_grid
+            }
+            Distribution::Int {
+                low, high, step, ..
+            } => {
+                value.fract() == 0.0
+                    && low as f64 <= value
+                    && value <= high as f64
+                    && (i128::from(value as i64) - i128::from(low)) % i128::from(step) == 0
+            }
+            Distribution::Categorical { choices } => {
+                value.fract() == 0.0 && 0.0 <= value && (value as usize) < choices.len()
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Checks whether `other` can be used for the same parameter name as `self`.
+    ///
+    /// The ranges may differ, but the kinds, the `log` flags and the categorical choices must be the same.
+    pub fn check_compatibility(&self, other: &Distribution) -> Result<()> {
+        match (self.to_modern(), other.to_modern()) {
+            (Distribution::Float { log: a, .. }, Distribution::Float { log: b, .. })
+            | (Distribution::Int { log: a, .. }, Distribution::Int { log: b, .. }) => {
+                track_assert_eq!(
+                    a,
+                    b,
+                    ErrorKind::InvalidInput,
+                    "Cannot set different log configuration to the same parameter name"
+                );
+            }
+            (
+                Distribution::Categorical { choices: a },
+                Distribution::Categorical { choices: b },
+            ) => {
+                track_assert_eq!(
+                    a,
+                    b,
+                    ErrorKind::InvalidInput,
+                    "Cannot set different categorical choices to the same parameter name"
+                );
+            }
+            (a, b) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Cannot set different distribution kind to the same parameter name: {:?}, {:?}",
+                a,
+                b
+            ),
+        }
+        Ok(())
+    }
+
+    /// Converts an internal parameter value into its external representation.
+    ///
+    /// For example, the internal value of a categorical parameter is the index of the chosen category.
+    pub fn to_external_repr(&self, value: f64) -> JsonValue {
+        match *self {
+            Distribution::IntUniform { .. } | Distribution::Int { .. } => (value as i64).into(),
+            Distribution::Categorical { ref choices } => choices
+                .get(value as usize)
+                .map_or(JsonValue::Null, |c| c.to_json()),
+            _ => value.into(),
+        }
+    }
+
+    /// Returns `true` if both distributions denote the same search space regardless of their formats.
+    pub fn is_equivalent(&self, other: &Distribution) -> bool {
+        self.to_modern() == other.to_modern()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistributionFormat {
+    Legacy,
+    Modern,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Category {
     Str(String),
     Float(f64),
+    Int(i64),
+    Bool(bool),
+    Null,
+}
+impl Category {
+    pub fn to_json(&self) -> JsonValue {
+        match *self {
+            Category::Str(ref v) => v.clone().into(),
+            Category::Float(v) => v.into(),
+            Category::Int(v) => v.into(),
+            Category::Bool(v) => v.into(),
+            Category::Null => JsonValue::Null,
+        }
+    }
+}
+
+fn default_int_step() -> i64 {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int_contains_works_for_wide_ranges() {
+        let distribution = Distribution::Int {
+            low: i64::MIN,
+            high: i64::MAX,
+            log: false,
+            step: 1,
+        };
+        assert!(distribution.contains(i64::MAX as f64));
+        assert!(distribution.contains(i64::MIN as f64));
+        assert!(distribution.contains(0.0));
+        assert!(!distribution.contains(0.5));
+
+        let distribution = Distribution::Int {
+            low: i64::MIN,
+            high: i64::MAX,
+            log: false,
+            step: 3,
+        };
+        assert!(distribution.contains((i64::MIN + 3 * (1 << 40)) as f64));
+        assert!(!distribution.contains((i64::MIN + 3 * (1 << 40) + 1024) as f64));
+    }
 }
@@ -1,9 +1,14 @@
+#[allow(clippy::single_component_path_imports)]
 use fibers;
+#[allow(clippy::single_component_path_imports)]
 use fibers_rpc;
+#[allow(clippy::single_component_path_imports)]
 use plumcast;
+#[allow(clippy::single_component_path_imports)]
 use std;
 use trackable::error::TrackableError;
 use trackable::error::{ErrorKind as TrackableErrorKind, ErrorKindExt};
+#[allow(clippy::single_component_path_imports)]
 use uuid;
 
 #[derive(Debug, Clone, TrackableError, Serialize, Deserialize)]
@@ -63,6 +68,12 @@ impl From<fibers::sync::oneshot::MonitorError<Error>> for Error {
 pub enum ErrorKind {
     AlreadyExists,
     NotFound,
+    InvalidInput,
+    LimitExceeded,
+    Unauthenticated,
+    PermissionDenied,
+    RateLimited,
+    InvalidState,
     Other,
 }
 impl TrackableErrorKind for ErrorKind {}
@@ -1,7 +1,16 @@
-use crate::study::{StudyId, StudyName};
+use crate::study::{Namespace, StudyId, StudyName};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Message {
-    CreateStudy { name: StudyName, id: StudyId },
-    JoinStudy { name: StudyName },
+    CreateStudy {
+        #[serde(default)]
+        namespace: Namespace,
+        name: StudyName,
+        id: StudyId,
+    },
+    JoinStudy {
+        #[serde(default)]
+        namespace: Namespace,
+        name: StudyName,
+    },
 }
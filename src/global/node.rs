@@ -1,8 +1,16 @@
+use crate::auth::TokenStore;
+use crate::cluster_key::{ClusterKey, SignedDecoderFactory, SignedEncoder};
 use crate::global::rpc;
 use crate::global::Message;
-use crate::study::{StudyId, StudyName, StudyNameAndId, StudyNode, StudyNodeHandle};
+use crate::message::UnionMessage;
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::study::{
+    Namespace, StudyId, StudyLifetime, StudyName, StudyNameAndId, StudyNode, StudyNodeHandle,
+};
+use crate::webhook::WebhookSender;
 use crate::{Error, ErrorKind, PlumcastNode, PlumcastServiceHandle, Result};
 use atomic_immut::AtomicImmut;
+use bytecodec::json_codec::JsonEncoder;
 use fibers::sync::{mpsc, oneshot};
 use fibers::time::timer::{self, Timeout};
 use fibers_global;
@@ -16,6 +24,7 @@ use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::Duration;
+use trackable::error::ErrorKindExt;
 
 #[derive(Debug)]
 struct Creating {
@@ -25,8 +34,11 @@ struct Creating {
     waitings: Vec<MessageId>,
 }
 
+type QualifiedName = (Namespace, StudyName);
+
 #[derive(Debug)]
 struct Joining {
+    namespace: Namespace,
     study_name: StudyName,
     timeout: Timeout,
     reply_tx: oneshot::Monitored<StudyId, Error>,
@@ -37,23 +49,80 @@ pub struct GlobalNodeBuilder {
     command_tx: mpsc::Sender<Command>,
     command_rx: mpsc::Receiver<Command>,
     studies: Arc<AtomicImmut<HashMap<StudyId, StudyNodeHandle>>>,
+    max_studies_per_namespace: Option<usize>,
+    auth_tokens: Option<Arc<TokenStore>>,
+    rate_limiter: Arc<RateLimiter>,
+    broadcast_limit: Option<RateLimit>,
+    study_lifetime: StudyLifetime,
+    cluster_key: Option<ClusterKey>,
 }
 impl GlobalNodeBuilder {
-    pub fn new(rpc: &mut RpcServerBuilder) -> Self {
+    /// Makes a new builder.
+    ///
+    /// If `cluster_key` is given, the RPC and gossip payloads of the node are authenticated by the key.
+    pub fn new(rpc: &mut RpcServerBuilder, cluster_key: Option<ClusterKey>) -> Self {
         let (command_tx, command_rx) = mpsc::channel();
         let studies = Default::default();
         let handle = GlobalNodeHandle {
             command_tx: command_tx.clone(),
             studies: Arc::clone(&studies),
+            auth_tokens: None,
+            rate_limiter: Default::default(),
+            cluster_key: cluster_key.clone(),
         };
-        rpc.add_cast_handler(rpc::RpcHandler::new(handle));
+        rpc.add_cast_handler_with_decoder(
+            rpc::RpcHandler::new(handle),
+            SignedDecoderFactory::new(cluster_key.clone()),
+        );
         Self {
             command_tx,
             command_rx,
             studies,
+            max_studies_per_namespace: None,
+            auth_tokens: None,
+            rate_limiter: Default::default(),
+            broadcast_limit: None,
+            study_lifetime: StudyLifetime::default(),
+            cluster_key,
         }
     }
 
+    /// Sets the maximum number of studies that can be created in a namespace.
+    ///
+    /// Note that only the studies known by this node are counted.
+    pub fn max_studies_per_namespace(&mut self, n: usize) -> &mut Self {
+        self.max_studies_per_namespace = Some(n);
+        self
+    }
+
+    /// Sets the bearer tokens accepted by the HTTP API.
+    pub fn auth_tokens(&mut self, tokens: TokenStore) -> &mut Self {
+        self.auth_tokens = Some(Arc::new(tokens));
+        self
+    }
+
+    /// Sets the rate limiter applied to the write requests to the HTTP API.
+    pub fn rate_limiter(&mut self, rate_limiter: RateLimiter) -> &mut Self {
+        self.rate_limiter = Arc::new(rate_limiter);
+        self
+    }
+
+    /// Sets the limit of broadcasts per second issued by each study node.
+    ///
+    /// Messages exceeding the limit are delayed rather than dropped.
+    pub fn broadcast_limit(&mut self, limit: RateLimit) -> &mut Self {
+        self.broadcast_limit = Some(limit);
+        self
+    }
+
+    /// Sets the default lifetime of the studies on this node.
+    ///
+    /// The default value is one hour.
+    pub fn study_lifetime(&mut self, lifetime: StudyLifetime) -> &mut Self {
+        self.study_lifetime = lifetime;
+        self
+    }
+
     pub fn finish(
         self,
         inner: PlumcastNode,
@@ -72,6 +141,13 @@ impl GlobalNodeBuilder {
             rpc,
             plumcast_service,
             studies: self.studies,
+            max_studies_per_namespace: self.max_studies_per_namespace,
+            auth_tokens: self.auth_tokens,
+            rate_limiter: self.rate_limiter,
+            broadcast_limit: self.broadcast_limit,
+            study_lifetime: self.study_lifetime,
+            webhook_sender: WebhookSender::start(),
+            cluster_key: self.cluster_key,
         }
     }
 }
@@ -81,22 +157,37 @@ pub struct GlobalNode {
     inner: PlumcastNode,
     command_tx: mpsc::Sender<Command>,
     command_rx: mpsc::Receiver<Command>,
-    creatings: HashMap<StudyName, Creating>,
+    creatings: HashMap<QualifiedName, Creating>,
     joinings: Vec<Joining>,
-    study_names: HashMap<StudyName, StudyId>,
+    study_names: HashMap<QualifiedName, StudyId>,
     studies: Arc<AtomicImmut<HashMap<StudyId, StudyNodeHandle>>>,
     forget_queue: VecDeque<(Duration, MessageId)>,
     rpc: RpcClientServiceHandle,
     plumcast_service: PlumcastServiceHandle,
+    max_studies_per_namespace: Option<usize>,
+    auth_tokens: Option<Arc<TokenStore>>,
+    rate_limiter: Arc<RateLimiter>,
+    broadcast_limit: Option<RateLimit>,
+    study_lifetime: StudyLifetime,
+    webhook_sender: WebhookSender,
+    cluster_key: Option<ClusterKey>,
 }
 impl GlobalNode {
     pub fn handle(&self) -> GlobalNodeHandle {
         GlobalNodeHandle {
             command_tx: self.command_tx.clone(),
             studies: Arc::clone(&self.studies),
+            auth_tokens: self.auth_tokens.clone(),
+            rate_limiter: Arc::clone(&self.rate_limiter),
+            cluster_key: self.cluster_key.clone(),
         }
     }
 
+    fn broadcast(&mut self, message: Message) {
+        let message = UnionMessage::from(message).sign(self.cluster_key.as_ref());
+        self.inner.broadcast(message);
+    }
+
     fn forget_time(&self) -> Duration {
         // TODO: parameterize
         self.inner.clock().now().as_duration() + Duration::from_secs(60)
@@ -105,42 +196,45 @@ impl GlobalNode {
     fn handle_message(&mut self, mid: MessageId, m: Message) -> Result<()> {
         self.forget_queue.push_back((self.forget_time(), mid));
         match m {
-            Message::CreateStudy { name, id } => {
-                if let Some(c) = self.creatings.remove(&name) {
+            Message::CreateStudy {
+                namespace,
+                name,
+                id,
+            } => {
+                let key = (namespace, name);
+                if let Some(c) = self.creatings.remove(&key) {
                     if c.study_id == id {
-                        self.creatings.insert(name.clone(), c);
+                        self.creatings.insert(key.clone(), c);
                     } else if c.study_id < id {
-                        self.notify_study(mid, name.clone(), c.study_id.clone(), None);
-                        self.creatings.insert(name.clone(), c);
+                        self.notify_study(mid, &key, c.study_id.clone(), None);
+                        self.creatings.insert(key.clone(), c);
                     } else {
-                        log::warn!(
-                            "Study {:?} is superseded by the other same name study",
-                            name
-                        );
+                        log::warn!("Study {:?} is superseded by the other same name study", key);
                         c.reply_tx.exit(Err(track!(Error::already_exists())));
                     }
                 }
-                if let Some(self_id) = self.study_names.get(&name).cloned() {
+                if let Some(self_id) = self.study_names.get(&key).cloned() {
                     if self_id != id {
                         log::warn!(
                             "Conflicted study {:?}: self={:?}, peer={:?}",
-                            name,
+                            key,
                             self_id,
                             id
                         );
                         let node_id = self.studies.load()[&self_id].node_id();
-                        self.notify_study(mid, name.clone(), self_id.clone(), Some(node_id));
+                        self.notify_study(mid, &key, self_id.clone(), Some(node_id));
                     }
                 } else {
                     assert!(!self.studies.load().contains_key(&id), "Study ID conflicts");
                 }
             }
-            Message::JoinStudy { name } => {
-                if let Some(id) = self.study_names.get(&name).cloned() {
+            Message::JoinStudy { namespace, name } => {
+                let key = (namespace, name);
+                if let Some(id) = self.study_names.get(&key).cloned() {
                     let node_id = self.studies.load()[&id].node_id();
-                    self.notify_study(mid, name.clone(), id.clone(), Some(node_id));
-                } else if let Some(c) = self.creatings.get_mut(&name) {
-                    log::info!("Add to waitings: {:?}, {:?}", name, mid);
+                    self.notify_study(mid, &key, id.clone(), Some(node_id));
+                } else if let Some(c) = self.creatings.get_mut(&key) {
+                    log::info!("Add to waitings: {:?}, {:?}", key, mid);
                     c.waitings.push(mid);
                 }
             }
@@ -151,62 +245,100 @@ impl GlobalNode {
     fn notify_study(
         &self,
         to: MessageId,
-        study_name: StudyName,
+        (namespace, study_name): &QualifiedName,
         study_id: StudyId,
         created: Option<NodeId>,
     ) {
-        let mut client = rpc::StudyCast::client(&self.rpc);
+        let encoder = SignedEncoder::new(JsonEncoder::new(), self.cluster_key.clone());
+        let mut client = rpc::StudyCast::client_with_encoder(&self.rpc, encoder);
         client.options_mut().force_wakeup = true;
         let study = StudyNameAndId {
-            study_name,
+            namespace: namespace.clone(),
+            study_name: study_name.clone(),
             study_id,
         };
         let _ = client.cast(to.node().address(), (study, created));
     }
 
+    fn count_studies(&self, namespace: &Namespace) -> usize {
+        let n_studies = self
+            .study_names
+            .keys()
+            .filter(|k| k.0 == *namespace)
+            .count();
+        let n_creatings = self.creatings.keys().filter(|k| k.0 == *namespace).count();
+        n_studies + n_creatings
+    }
+
+    #[allow(clippy::redundant_pattern_matching)]
     fn handle_command(&mut self, command: Command) {
         match command {
             Command::CreateStudy {
+                namespace,
                 name,
                 id,
                 wait_time,
                 reply_tx,
             } => {
-                log::info!("Try creating new study: {:?}, {:?}", name, id);
-                if self.creatings.contains_key(&name) {
-                    log::warn!("Study {:?} is already creating", name);
+                log::info!(
+                    "Try creating new study: {:?}, {:?}, {:?}",
+                    namespace,
+                    name,
+                    id
+                );
+                let key = (namespace, name);
+                if self.creatings.contains_key(&key) {
+                    log::warn!("Study {:?} is already creating", key);
                     reply_tx.exit(Err(track!(Error::already_exists())));
                     return;
                 }
+                if let Some(limit) = self.max_studies_per_namespace {
+                    if self.count_studies(&key.0) >= limit {
+                        let e = ErrorKind::LimitExceeded.cause(format!(
+                            "Too many studies in the namespace {:?} (limit={})",
+                            key.0.as_str(),
+                            limit
+                        ));
+                        reply_tx.exit(Err(track!(Error::from(e))));
+                        return;
+                    }
+                }
 
                 let m = Message::CreateStudy {
-                    name: name.clone(),
+                    namespace: key.0.clone(),
+                    name: key.1.clone(),
                     id: id.clone(),
                 };
-                self.inner.broadcast(m.into());
+                self.broadcast(m);
                 let creating = Creating {
                     study_id: id,
                     timeout: timer::timeout(wait_time),
                     reply_tx,
                     waitings: Vec::new(),
                 };
-                self.creatings.insert(name, creating);
+                self.creatings.insert(key, creating);
             }
             Command::JoinStudy {
+                namespace,
                 name,
                 wait_time,
                 reply_tx,
             } => {
-                if let Some(id) = self.study_names.get(&name).cloned() {
+                let key = (namespace, name);
+                if let Some(id) = self.study_names.get(&key).cloned() {
                     reply_tx.exit(Ok(id));
                     return;
                 }
 
-                log::info!("Starts finding the study: {:?}", name);
-                let m = Message::JoinStudy { name: name.clone() };
-                self.inner.broadcast(m.into());
+                log::info!("Starts finding the study: {:?}", key);
+                let m = Message::JoinStudy {
+                    namespace: key.0.clone(),
+                    name: key.1.clone(),
+                };
+                self.broadcast(m);
                 let joining = Joining {
-                    study_name: name,
+                    namespace: key.0,
+                    study_name: key.1,
                     timeout: timer::timeout(wait_time),
                     reply_tx,
                 };
@@ -217,23 +349,26 @@ impl GlobalNode {
                     .study_names
                     .iter()
                     .map(|x| StudyNameAndId {
-                        study_name: x.0.clone(),
+                        namespace: (x.0).0.clone(),
+                        study_name: (x.0).1.clone(),
                         study_id: x.1.clone(),
                     })
                     .collect();
                 reply_tx.exit(Ok(studies));
             }
             Command::NotifyStudy { study, created } => {
-                if let Some(c) = self.creatings.remove(&study.study_name) {
+                let key = qualified_name(&study);
+                if let Some(c) = self.creatings.remove(&key) {
                     log::info!("Study already exists: {:?}", study);
                     c.reply_tx.exit(Err(track!(Error::already_exists())));
                 }
                 if created.is_some() {
                     let mut i = 0;
                     while i < self.joinings.len() {
-                        if self.joinings[i].study_name == study.study_name {
+                        let j = &self.joinings[i];
+                        if j.namespace == study.namespace && j.study_name == study.study_name {
                             let j = self.joinings.swap_remove(i);
-                            if !self.study_names.contains_key(&j.study_name) {
+                            if !self.study_names.contains_key(&key) {
                                 self.spawn_study_node(study.clone(), created);
                             }
                             j.reply_tx.exit(Ok(study.study_id.clone()));
@@ -249,7 +384,7 @@ impl GlobalNode {
             }
             Command::NotifyStudyNodeDown { study } => {
                 log::info!("Study node terminated: {:?}", study);
-                self.study_names.remove(&study.study_name);
+                self.study_names.remove(&qualified_name(&study));
                 self.studies.update(|x| {
                     let mut x = x.clone();
                     x.remove(&study.study_id);
@@ -268,11 +403,18 @@ impl GlobalNode {
         }
 
         let handle = self.handle();
-        let study_node = StudyNode::new(study.clone(), node);
+        let study_node = StudyNode::new(
+            study.clone(),
+            node,
+            self.broadcast_limit,
+            self.study_lifetime,
+            self.webhook_sender.clone(),
+            self.cluster_key.clone(),
+        );
 
         let study_node_handle = study_node.handle();
         self.study_names
-            .insert(study.study_name.clone(), study.study_id.clone());
+            .insert(qualified_name(&study), study.study_id.clone());
         assert!(
             !self.studies.load().contains_key(&study.study_id),
             "ID conflicts"
@@ -292,29 +434,31 @@ impl GlobalNode {
         }));
     }
 
+    #[allow(clippy::clone_on_copy)]
     fn handle_creatings(&mut self) -> Result<bool> {
         let mut did_something = false;
         let mut timeouts = Vec::new();
-        for (name, c) in self.creatings.iter_mut() {
+        for (key, c) in self.creatings.iter_mut() {
             if track!(c.timeout.poll().map_err(Error::from))?.is_ready() {
                 did_something = true;
-                timeouts.push(name.clone());
+                timeouts.push(key.clone());
             }
         }
-        for name in timeouts {
-            log::info!("New study is created: {:?}", name);
+        for key in timeouts {
+            log::info!("New study is created: {:?}", key);
 
-            let c = self.creatings.remove(&name).expect("never fails");
+            let c = self.creatings.remove(&key).expect("never fails");
             self.spawn_study_node(
                 StudyNameAndId {
-                    study_name: name.clone(),
+                    namespace: key.0.clone(),
+                    study_name: key.1.clone(),
                     study_id: c.study_id.clone(),
                 },
                 None,
             );
             let node_id = self.studies.load()[&c.study_id].node_id();
             for w in c.waitings {
-                self.notify_study(w, name.clone(), c.study_id.clone(), Some(node_id.clone()));
+                self.notify_study(w, &key, c.study_id.clone(), Some(node_id.clone()));
             }
             c.reply_tx.exit(Ok(()));
         }
@@ -336,6 +480,7 @@ impl GlobalNode {
         Ok(did_something)
     }
 
+    #[allow(clippy::unnecessary_map_or)]
     fn handle_forget(&mut self) {
         while self
             .forget_queue
@@ -351,6 +496,7 @@ impl Future for GlobalNode {
     type Item = ();
     type Error = Error;
 
+    #[allow(clippy::clone_on_copy, clippy::collapsible_if)]
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         let mut did_something = true;
         while did_something {
@@ -359,7 +505,15 @@ impl Future for GlobalNode {
             while let Async::Ready(Some(message)) = track!(self.inner.poll())? {
                 did_something = true;
                 let id = message.id().clone();
-                let payload = track!(message.into_payload().into_global_message())?;
+                let payload = message.into_payload();
+                let payload = match track!(payload.verify(self.cluster_key.as_ref())) {
+                    Ok(payload) => track!(payload.into_global_message())?,
+                    Err(e) => {
+                        log::warn!("Drops an unverified message: {}", e);
+                        self.inner.forget_message(&id);
+                        continue;
+                    }
+                };
                 track!(self.handle_message(id, payload))?;
             }
             while let Async::Ready(Some(command)) = self.command_rx.poll().expect("never fails") {
@@ -386,16 +540,37 @@ impl Future for GlobalNode {
 pub struct GlobalNodeHandle {
     command_tx: mpsc::Sender<Command>,
     studies: Arc<AtomicImmut<HashMap<StudyId, StudyNodeHandle>>>,
+    auth_tokens: Option<Arc<TokenStore>>,
+    rate_limiter: Arc<RateLimiter>,
+    cluster_key: Option<ClusterKey>,
 }
 impl GlobalNodeHandle {
+    /// Returns the bearer tokens accepted by the HTTP API (`None` means that authentication is disabled).
+    pub fn auth_tokens(&self) -> Option<&TokenStore> {
+        self.auth_tokens.as_deref()
+    }
+
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    /// Returns the number of payloads dropped by this node because they failed verification with the cluster key.
+    pub fn rejected_messages(&self) -> u64 {
+        self.cluster_key
+            .as_ref()
+            .map_or(0, |key| key.rejected_messages())
+    }
+
     pub fn create_study(
         &self,
+        namespace: Namespace,
         name: StudyName,
         wait_time: Duration,
     ) -> impl Future<Item = StudyId, Error = Error> {
         let id = StudyId::new();
         let (reply_tx, reply_rx) = oneshot::monitor();
         let command = Command::CreateStudy {
+            namespace,
             name,
             id: id.clone(),
             wait_time,
@@ -407,11 +582,13 @@ impl GlobalNodeHandle {
 
     pub fn join_study(
         &self,
+        namespace: Namespace,
         name: StudyName,
         wait_time: Duration,
     ) -> impl Future<Item = StudyId, Error = Error> {
         let (reply_tx, reply_rx) = oneshot::monitor();
         let command = Command::JoinStudy {
+            namespace,
             name,
             wait_time,
             reply_tx,
@@ -449,12 +626,14 @@ impl GlobalNodeHandle {
 #[derive(Debug)]
 enum Command {
     CreateStudy {
+        namespace: Namespace,
         name: StudyName,
         id: StudyId,
         wait_time: Duration,
         reply_tx: oneshot::Monitored<(), Error>,
     },
     JoinStudy {
+        namespace: Namespace,
         name: StudyName,
         wait_time: Duration,
         reply_tx: oneshot::Monitored<StudyId, Error>,
@@ -470,3 +649,7 @@ enum Command {
         study: StudyNameAndId,
     },
 }
+
+fn qualified_name(study: &StudyNameAndId) -> QualifiedName {
+    (study.namespace.clone(), study.study_name.clone())
+}
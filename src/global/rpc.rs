@@ -1,3 +1,4 @@
+use crate::cluster_key::{SignedDecoder, SignedEncoder};
 use crate::global::GlobalNodeHandle;
 use crate::study::StudyNameAndId;
 use bytecodec::json_codec::{JsonDecoder, JsonEncoder};
@@ -22,8 +23,8 @@ impl Cast for StudyCast {
     const NAME: &'static str = "plumtuna.global.study";
 
     type Notification = (StudyNameAndId, Option<NodeId>);
-    type Encoder = JsonEncoder<Self::Notification>;
-    type Decoder = JsonDecoder<Self::Notification>;
+    type Encoder = SignedEncoder<JsonEncoder<Self::Notification>>;
+    type Decoder = SignedDecoder<JsonDecoder<Self::Notification>>;
 }
 impl HandleCast<StudyCast> for RpcHandler {
     fn handle_cast(&self, (study, created): (StudyNameAndId, Option<NodeId>)) -> NoReply {
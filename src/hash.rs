@@ -0,0 +1,8 @@
+/// Returns the 64-bit FNV-1a hash of `bytes`.
+///
+/// Unlike `DefaultHasher`, the algorithm is fixed, so the values are the same across builds and nodes.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
@@ -1,16 +1,31 @@
+use crate::auth::{Acl, Permission, Scope};
+use crate::distribution::{Distribution, DistributionFormat};
 use crate::global::GlobalNodeHandle;
-use crate::study::{self, StudyDirection, StudyName, StudyNameAndId, StudySummary, SubscribeId};
+use crate::importance::{self, ParamImportance};
+use crate::learning_curve::{self, LearningCurves};
+use crate::pruner::Pruner;
+use crate::sampler::Sampler;
+use crate::search_space::{SearchSpace, SearchSpaceMode};
+use crate::study::{
+    self, Namespace, StudyDirection, StudyId, StudyLifetime, StudyName, StudyNameAndId,
+    StudyNodeHandle, StudyState, StudyStats, StudySummary, SubscribeId, TrialBudget,
+};
 use crate::trial::{Trial, TrialId, TrialParamValue, TrialState};
+use crate::warm_start::TrialFilter;
+use crate::webhook::Webhook;
 use crate::{Error, ErrorKind, Result};
 use bytecodec::json_codec::{JsonDecoder, JsonEncoder};
 use bytecodec::marker::Never;
 use bytecodec::null::NullDecoder;
 use fibers_http_server::{HandleRequest, Reply, Req, Res, Status};
-use futures::future::{done, ok};
+use futures::future::{done, join_all, ok};
 use futures::Future;
-use httpcodec::{BodyDecoder, BodyEncoder};
+use httpcodec::{
+    BodyDecoder, BodyEncoder, HeaderField, HttpVersion, ReasonPhrase, Response, StatusCode,
+};
 use serde_json::Value as JsonValue;
 use std;
+use std::collections::{BTreeMap, HashMap};
 use std::time::Duration;
 use trackable::error::ErrorKindExt;
 use url::{self, Url};
@@ -26,6 +41,22 @@ macro_rules! http_try {
     };
 }
 
+/// The header to select the namespace of study names.
+pub const NAMESPACE_HEADER: &str = "X-Plumtuna-Namespace";
+
+/// The header to identify the client that issues write requests (recorded in the audit log of studies).
+///
+/// The header is self-declared, so the identities taken from it are recorded as unverified
+/// and are never confused with the principals of tokens.
+pub const CLIENT_HEADER: &str = "X-Client";
+
+/// The write endpoints that are available regardless of the lifecycle state of the study.
+const STATE_INDEPENDENT_PATHS: &[&str] = &[
+    "/studies/*/state",
+    "/studies/*/lifetime",
+    "/studies/*/keepalive",
+];
+
 pub struct PostStudy(pub GlobalNodeHandle);
 impl HandleRequest for PostStudy {
     const METHOD: &'static str = "POST";
@@ -39,14 +70,99 @@ impl HandleRequest for PostStudy {
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let wait_time = Duration::from_secs(1); // TODO
+        let namespace = http_try!(get_namespace(&req));
         let future = self
             .0
-            .create_study(req.into_body().study_name, wait_time)
+            .create_study(namespace, req.into_body().study_name, wait_time)
             .map(|study_id| PostStudyRes { study_id });
         Box::new(track_err!(future).then(into_http_response))
     }
 }
 
+pub struct PostStudyClone(pub GlobalNodeHandle);
+impl HandleRequest for PostStudyClone {
+    const METHOD: &'static str = "POST";
+    const PATH: &'static str = "/studies/*/clone";
+
+    type ReqBody = PostStudyReq;
+    type ResBody = HttpResult<PostStudyRes>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let source = http_try!(get_study_node(&self.0, &study_id, &req));
+        let wait_time = Duration::from_secs(1); // TODO
+        let namespace = http_try!(get_namespace(&req));
+        let global = self.0.clone();
+        let future = self
+            .0
+            .create_study(namespace, req.into_body().study_name, wait_time)
+            .and_then(move |study_id| {
+                let target = track!(global.get_study_node(&study_id))?;
+                Ok((study_id, target))
+            })
+            .and_then(move |(study_id, target)| {
+                source
+                    .clone_messages(study_id.clone())
+                    .map(move |messages| {
+                        target.broadcast_messages(messages);
+                        PostStudyRes { study_id }
+                    })
+            });
+        Box::new(track_err!(future).then(into_http_response))
+    }
+}
+
+pub struct PostStudyWarmStart(pub GlobalNodeHandle);
+impl HandleRequest for PostStudyWarmStart {
+    const METHOD: &'static str = "POST";
+    const PATH: &'static str = "/studies/*/warm_start";
+
+    type ReqBody = PostStudyWarmStartReq;
+    type ResBody = HttpResult<Vec<TrialId>>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let target = http_try!(get_study_node(&self.0, &study_id, &req));
+        let principal = get_principal(&self.0, &req);
+        let req = req.into_body();
+        let mut sources = Vec::new();
+        for source_id in &req.source_study_ids {
+            let source = http_try!(self.0.get_study_node(source_id));
+            http_try!(source.authorize(principal.as_deref(), Permission::Read));
+            sources.push(source);
+        }
+
+        let future = target
+            .get_summary()
+            .and_then(move |summary| {
+                let filter = TrialFilter {
+                    direction: summary.direction,
+                    value_threshold: req.value_threshold,
+                    top_k: req.top_k,
+                    search_space: summary.search_space,
+                    min_param_overlap: req.min_param_overlap,
+                };
+                track!(filter.validate())?;
+                Ok(filter)
+            })
+            .and_then(move |filter| {
+                join_all(
+                    sources
+                        .into_iter()
+                        .map(move |s| s.select_trials(filter.clone())),
+                )
+            })
+            .and_then(move |trials| target.import_trials(trials.into_iter().flatten().collect()));
+        Box::new(track_err!(future).then(into_http_response))
+    }
+}
+
 pub struct GetStudyByName(pub GlobalNodeHandle);
 impl HandleRequest for GetStudyByName {
     const METHOD: &'static str = "GET";
@@ -60,11 +176,16 @@ impl HandleRequest for GetStudyByName {
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let wait_time = Duration::from_millis(1500); // TODO
+        let namespace = http_try!(get_namespace(&req));
         let study_name = http_try!(get_study_name(req.url()));
-        let future = track_err!(self.0.join_study(study_name.clone(), wait_time));
+        let future =
+            track_err!(self
+                .0
+                .join_study(namespace.clone(), study_name.clone(), wait_time));
         Box::new(
             future
                 .map(move |study_id| StudyNameAndId {
+                    namespace,
                     study_name,
                     study_id,
                 })
@@ -79,42 +200,518 @@ impl HandleRequest for GetStudies {
     const PATH: &'static str = "/studies";
 
     type ReqBody = ();
-    type ResBody = HttpResult<Vec<StudyNameAndId>>;
+    type ResBody = HttpResult<Vec<StudyListEntry>>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let namespace = if req.header().get_field(NAMESPACE_HEADER).is_some() {
+            Some(http_try!(get_namespace(&req)))
+        } else {
+            None
+        };
+        let future = http_try!(list_studies(&self.0, namespace, &req));
+        Box::new(track_err!(future).then(into_http_response))
+    }
+}
+
+pub struct GetNamespaceStudies(pub GlobalNodeHandle);
+impl HandleRequest for GetNamespaceStudies {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/namespaces/*/studies";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<Vec<StudyListEntry>>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let namespace = http_try!(get_namespace_from_path(req.url()));
+        let future = http_try!(list_studies(&self.0, Some(namespace), &req));
+        Box::new(track_err!(future).then(into_http_response))
+    }
+}
+
+pub struct GetNamespaces(pub GlobalNodeHandle);
+impl HandleRequest for GetNamespaces {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/namespaces";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<Vec<NamespaceEntry>>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, _req: Req<Self::ReqBody>) -> Self::Reply {
+        let future = self.0.get_studies().map(|studies| {
+            let mut counts = BTreeMap::new();
+            for s in studies {
+                *counts.entry(s.namespace).or_insert(0) += 1;
+            }
+            counts
+                .into_iter()
+                .map(|(namespace, n_studies)| NamespaceEntry {
+                    namespace,
+                    n_studies,
+                })
+                .collect::<Vec<_>>()
+        });
+        Box::new(track_err!(future).then(into_http_response))
+    }
+}
+
+pub struct GetClusterStats(pub GlobalNodeHandle);
+impl HandleRequest for GetClusterStats {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/cluster/stats";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<ClusterStats>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, _req: Req<Self::ReqBody>) -> Self::Reply {
+        let stats = ClusterStats {
+            rejected_messages: self.0.rejected_messages(),
+        };
+        Box::new(ok(http_ok(stats)))
+    }
+}
+
+/// Lists the summaries of the studies that satisfy the conditions given by `namespace` and the query of `req`
+/// and that the client of `req` has the read permission on.
+fn list_studies<T>(
+    global: &GlobalNodeHandle,
+    namespace: Option<Namespace>,
+    req: &Req<T>,
+) -> Result<impl Future<Item = Vec<StudyListEntry>, Error = Error>> {
+    let url = req.url();
+    let tags = track!(get_tag_filters(url))?;
+    let prefix = url_query_value(url, "prefix").unwrap_or_default();
+    let principal = get_principal(global, req);
+    let global = global.clone();
+    let future = global
+        .get_studies()
+        .and_then(move |studies| {
+            let summaries = studies
+                .into_iter()
+                .filter(|s| namespace.as_ref().is_none_or(|n| s.namespace == *n))
+                .filter(|s| s.study_name.as_str().starts_with(&prefix))
+                .filter_map(|s| global.get_study_node(&s.study_id).ok())
+                .filter(|node| {
+                    node.authorize(principal.as_deref(), Permission::Read)
+                        .is_ok()
+                })
+                .map(|node| node.get_summary().then(|r| Ok(r.ok())))
+                .collect::<Vec<_>>();
+            join_all(summaries)
+        })
+        .map(move |summaries| {
+            let mut entries = summaries
+                .into_iter()
+                .flatten()
+                .filter(|s| {
+                    tags.iter().all(|(k, v)| {
+                        s.tags
+                            .get(k)
+                            .is_some_and(|x| v.as_ref().is_none_or(|v| x == v))
+                    })
+                })
+                .map(StudyListEntry::from)
+                .collect::<Vec<_>>();
+            entries
+                .sort_by(|a, b| (&a.namespace, &a.study_name).cmp(&(&b.namespace, &b.study_name)));
+            entries
+        });
+    Ok(future)
+}
+
+pub struct GetStudy(pub GlobalNodeHandle);
+impl HandleRequest for GetStudy {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/studies/*";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<StudySummary>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let future = track_err!(study_node.get_summary());
+        Box::new(future.then(into_http_response))
+    }
+}
+
+pub struct PutStudyDirection(pub GlobalNodeHandle);
+impl HandleRequest for PutStudyDirection {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/studies/*/direction";
+
+    type ReqBody = StudyDirection;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let direction = req.into_body();
+        study_node.set_study_direction(direction);
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct PutStudyUserAttr(pub GlobalNodeHandle);
+impl HandleRequest for PutStudyUserAttr {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/studies/*/user_attrs/*";
+
+    type ReqBody = JsonValue;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let key = http_try!(get_attr_key(req.url()));
+        let value = req.into_body();
+        study_node.set_study_user_attr(key, value);
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct PutStudyTag(pub GlobalNodeHandle);
+impl HandleRequest for PutStudyTag {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/studies/*/tags/*";
+
+    type ReqBody = String;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let key = http_try!(get_attr_key(req.url()));
+        let value = req.into_body();
+        study_node.set_study_tag(key, Some(value));
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct DeleteStudyTag(pub GlobalNodeHandle);
+impl HandleRequest for DeleteStudyTag {
+    const METHOD: &'static str = "DELETE";
+    const PATH: &'static str = "/studies/*/tags/*";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let key = http_try!(get_attr_key(req.url()));
+        study_node.set_study_tag(key, None);
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct GetStudyWebhooks(pub GlobalNodeHandle);
+impl HandleRequest for GetStudyWebhooks {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/studies/*/webhooks";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<BTreeMap<String, Webhook>>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let future = track_err!(study_node.get_webhooks());
+        Box::new(future.then(into_http_response))
+    }
+}
+
+pub struct PutStudyWebhook(pub GlobalNodeHandle);
+impl HandleRequest for PutStudyWebhook {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/studies/*/webhooks/*";
+
+    type ReqBody = Webhook;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let name = http_try!(get_attr_key(req.url()));
+        let webhook = req.into_body();
+        http_try!(webhook.validate());
+        study_node.set_study_webhook(name, Some(webhook));
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct DeleteStudyWebhook(pub GlobalNodeHandle);
+impl HandleRequest for DeleteStudyWebhook {
+    const METHOD: &'static str = "DELETE";
+    const PATH: &'static str = "/studies/*/webhooks/*";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let name = http_try!(get_attr_key(req.url()));
+        study_node.set_study_webhook(name, None);
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct GetStudyAcl(pub GlobalNodeHandle);
+impl HandleRequest for GetStudyAcl {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/studies/*/acl";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<Acl>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        Box::new(ok(http_ok(study_node.acl())))
+    }
+}
+
+pub struct PutStudyAcl(pub GlobalNodeHandle);
+impl HandleRequest for PutStudyAcl {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/studies/*/acl/*";
+
+    type ReqBody = Permission;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let principal = http_try!(get_attr_key(req.url()));
+        let permission = req.into_body();
+        study_node.set_study_acl(principal, Some(permission));
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct DeleteStudyAcl(pub GlobalNodeHandle);
+impl HandleRequest for DeleteStudyAcl {
+    const METHOD: &'static str = "DELETE";
+    const PATH: &'static str = "/studies/*/acl/*";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let principal = http_try!(get_attr_key(req.url()));
+        study_node.set_study_acl(principal, None);
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct PutStudySystemAttr(pub GlobalNodeHandle);
+impl HandleRequest for PutStudySystemAttr {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/studies/*/system_attrs/*";
+
+    type ReqBody = JsonValue;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let key = http_try!(get_attr_key(req.url()));
+        let value = req.into_body();
+        study_node.set_study_system_attr(key, value);
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct PutStudySampler(pub GlobalNodeHandle);
+impl HandleRequest for PutStudySampler {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/studies/*/sampler";
+
+    type ReqBody = Sampler;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let sampler = req.into_body();
+        study_node.set_study_sampler(sampler);
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct PutStudyTrialBudget(pub GlobalNodeHandle);
+impl HandleRequest for PutStudyTrialBudget {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/studies/*/trial_budget";
+
+    type ReqBody = TrialBudget;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        study_node.set_study_trial_budget(Some(req.into_body()));
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct DeleteStudyTrialBudget(pub GlobalNodeHandle);
+impl HandleRequest for DeleteStudyTrialBudget {
+    const METHOD: &'static str = "DELETE";
+    const PATH: &'static str = "/studies/*/trial_budget";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        study_node.set_study_trial_budget(None);
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct PutStudyState(pub GlobalNodeHandle);
+impl HandleRequest for PutStudyState {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/studies/*/state";
+
+    type ReqBody = StudyState;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        study_node.set_study_state(req.into_body());
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct PutStudyLifetime(pub GlobalNodeHandle);
+impl HandleRequest for PutStudyLifetime {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/studies/*/lifetime";
+
+    type ReqBody = StudyLifetime;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        study_node.set_study_lifetime(Some(req.into_body()));
+        Box::new(ok(http_ok(())))
+    }
+}
+
+pub struct DeleteStudyLifetime(pub GlobalNodeHandle);
+impl HandleRequest for DeleteStudyLifetime {
+    const METHOD: &'static str = "DELETE";
+    const PATH: &'static str = "/studies/*/lifetime";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<()>;
     type Decoder = BodyDecoder<NullDecoder>;
     type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
     type Reply = Reply<Self::ResBody>;
 
-    fn handle_request(&self, _req: Req<Self::ReqBody>) -> Self::Reply {
-        let future = track_err!(self.0.get_studies());
-        Box::new(future.then(into_http_response))
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        study_node.set_study_lifetime(None);
+        Box::new(ok(http_ok(())))
     }
 }
 
-pub struct GetStudy(pub GlobalNodeHandle);
-impl HandleRequest for GetStudy {
-    const METHOD: &'static str = "GET";
-    const PATH: &'static str = "/studies/*";
+pub struct PostStudyKeepAlive(pub GlobalNodeHandle);
+impl HandleRequest for PostStudyKeepAlive {
+    const METHOD: &'static str = "POST";
+    const PATH: &'static str = "/studies/*/keepalive";
 
     type ReqBody = ();
-    type ResBody = HttpResult<StudySummary>;
+    type ResBody = HttpResult<()>;
     type Decoder = BodyDecoder<NullDecoder>;
     type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
     type Reply = Reply<Self::ResBody>;
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let study_id = http_try!(get_study_id(req.url()));
-        let study_node = http_try!(self.0.get_study_node(&study_id));
-        let future = track_err!(study_node.get_summary());
-        Box::new(future.then(into_http_response))
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        study_node.keep_alive();
+        Box::new(ok(http_ok(())))
     }
 }
 
-pub struct PutStudyDirection(pub GlobalNodeHandle);
-impl HandleRequest for PutStudyDirection {
+pub struct PutStudyPruner(pub GlobalNodeHandle);
+impl HandleRequest for PutStudyPruner {
     const METHOD: &'static str = "PUT";
-    const PATH: &'static str = "/studies/*/direction";
+    const PATH: &'static str = "/studies/*/pruner";
 
-    type ReqBody = StudyDirection;
+    type ReqBody = Pruner;
     type ResBody = HttpResult<()>;
     type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
     type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
@@ -122,19 +719,20 @@ impl HandleRequest for PutStudyDirection {
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let study_id = http_try!(get_study_id(req.url()));
-        let study_node = http_try!(self.0.get_study_node(&study_id));
-        let direction = req.into_body();
-        study_node.set_study_direction(direction);
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let pruner = req.into_body();
+        http_try!(pruner.validate());
+        study_node.set_study_pruner(pruner);
         Box::new(ok(http_ok(())))
     }
 }
 
-pub struct PutStudyUserAttr(pub GlobalNodeHandle);
-impl HandleRequest for PutStudyUserAttr {
+pub struct PutStudySearchSpace(pub GlobalNodeHandle);
+impl HandleRequest for PutStudySearchSpace {
     const METHOD: &'static str = "PUT";
-    const PATH: &'static str = "/studies/*/user_attrs/*";
+    const PATH: &'static str = "/studies/*/search_space";
 
-    type ReqBody = JsonValue;
+    type ReqBody = SearchSpace;
     type ResBody = HttpResult<()>;
     type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
     type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
@@ -142,32 +740,35 @@ impl HandleRequest for PutStudyUserAttr {
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let study_id = http_try!(get_study_id(req.url()));
-        let study_node = http_try!(self.0.get_study_node(&study_id));
-        let key = http_try!(get_attr_key(req.url()));
-        let value = req.into_body();
-        study_node.set_study_user_attr(key, value);
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let search_space = req.into_body();
+        for (key, distribution) in &search_space {
+            http_try!(distribution
+                .validate()
+                .map_err(|e| track!(e, "key={:?}", key)));
+        }
+        study_node.set_study_search_space(search_space);
         Box::new(ok(http_ok(())))
     }
 }
 
-pub struct PutStudySystemAttr(pub GlobalNodeHandle);
-impl HandleRequest for PutStudySystemAttr {
-    const METHOD: &'static str = "PUT";
-    const PATH: &'static str = "/studies/*/system_attrs/*";
+pub struct GetStudySearchSpace(pub GlobalNodeHandle);
+impl HandleRequest for GetStudySearchSpace {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/studies/*/search_space";
 
-    type ReqBody = JsonValue;
-    type ResBody = HttpResult<()>;
-    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type ReqBody = ();
+    type ResBody = HttpResult<SearchSpace>;
+    type Decoder = BodyDecoder<NullDecoder>;
     type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
     type Reply = Reply<Self::ResBody>;
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let study_id = http_try!(get_study_id(req.url()));
-        let study_node = http_try!(self.0.get_study_node(&study_id));
-        let key = http_try!(get_attr_key(req.url()));
-        let value = req.into_body();
-        study_node.set_study_system_attr(key, value);
-        Box::new(ok(http_ok(())))
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let mode = http_try!(get_search_space_mode(req.url()));
+        let future = track_err!(study_node.get_search_space(mode));
+        Box::new(future.then(into_http_response))
     }
 }
 
@@ -184,10 +785,10 @@ impl HandleRequest for PostTrial {
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let study_id = http_try!(get_study_id(req.url()));
-        let study_node = http_try!(self.0.get_study_node(&study_id));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
         let trial_id = TrialId::new(&study_id);
-        study_node.create_trial(trial_id.clone());
-        Box::new(ok(http_ok(trial_id)))
+        let future = study_node.create_trial(trial_id.clone()).map(|()| trial_id);
+        Box::new(track_err!(future).then(into_http_response))
     }
 }
 
@@ -204,9 +805,9 @@ impl HandleRequest for PutTrialState {
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let trial_id = http_try!(get_trial_id(req.url()));
-        let state = req.into_body();
         let study_id = http_try!(trial_id.get_study_id());
-        let study_node = http_try!(self.0.get_study_node(&study_id));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let state = req.into_body();
         study_node.set_trial_state(trial_id.clone(), state);
         Box::new(ok(http_ok(())))
     }
@@ -226,12 +827,12 @@ impl HandleRequest for PutTrialParam {
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let trial_id = http_try!(get_trial_id(req.url()));
         let study_id = http_try!(trial_id.get_study_id());
-        let study_node = http_try!(self.0.get_study_node(&study_id));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
 
         let key = http_try!(get_attr_key(req.url()));
         let value = req.into_body();
-        study_node.set_trial_param(trial_id, key, value);
-        Box::new(ok(http_ok(())))
+        let future = study_node.set_trial_param(trial_id, key, value);
+        Box::new(track_err!(future).then(into_http_response))
     }
 }
 
@@ -249,7 +850,7 @@ impl HandleRequest for PutTrialValue {
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let trial_id = http_try!(get_trial_id(req.url()));
         let study_id = http_try!(trial_id.get_study_id());
-        let study_node = http_try!(self.0.get_study_node(&study_id));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
 
         let value = req.into_body();
         study_node.set_trial_value(trial_id, value);
@@ -257,6 +858,28 @@ impl HandleRequest for PutTrialValue {
     }
 }
 
+pub struct PutTrialConstraints(pub GlobalNodeHandle);
+impl HandleRequest for PutTrialConstraints {
+    const METHOD: &'static str = "PUT";
+    const PATH: &'static str = "/trials/*/constraints";
+
+    type ReqBody = Vec<f64>;
+    type ResBody = HttpResult<()>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let trial_id = http_try!(get_trial_id(req.url()));
+        let study_id = http_try!(trial_id.get_study_id());
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+
+        let constraints = req.into_body();
+        study_node.set_trial_constraints(trial_id, constraints);
+        Box::new(ok(http_ok(())))
+    }
+}
+
 pub struct PutTrialIntermediateValue(pub GlobalNodeHandle);
 impl HandleRequest for PutTrialIntermediateValue {
     const METHOD: &'static str = "PUT";
@@ -271,7 +894,7 @@ impl HandleRequest for PutTrialIntermediateValue {
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let trial_id = http_try!(get_trial_id(req.url()));
         let study_id = http_try!(trial_id.get_study_id());
-        let study_node = http_try!(self.0.get_study_node(&study_id));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
 
         let step = http_try!(get_step(req.url()));
         let value = req.into_body();
@@ -294,7 +917,7 @@ impl HandleRequest for PutTrialSystemAttr {
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let trial_id = http_try!(get_trial_id(req.url()));
         let study_id = http_try!(trial_id.get_study_id());
-        let study_node = http_try!(self.0.get_study_node(&study_id));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
 
         let key = http_try!(get_attr_key(req.url()));
         let value = req.into_body();
@@ -317,7 +940,7 @@ impl HandleRequest for PutTrialUserAttr {
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let trial_id = http_try!(get_trial_id(req.url()));
         let study_id = http_try!(trial_id.get_study_id());
-        let study_node = http_try!(self.0.get_study_node(&study_id));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
 
         let key = http_try!(get_attr_key(req.url()));
         let value = req.into_body();
@@ -326,6 +949,73 @@ impl HandleRequest for PutTrialUserAttr {
     }
 }
 
+pub struct PostTrialSample(pub GlobalNodeHandle);
+impl HandleRequest for PostTrialSample {
+    const METHOD: &'static str = "POST";
+    const PATH: &'static str = "/trials/*/sample";
+
+    type ReqBody = BTreeMap<String, Distribution>;
+    type ResBody = HttpResult<HashMap<String, TrialParamValue>>;
+    type Decoder = BodyDecoder<JsonDecoder<Self::ReqBody>>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let trial_id = http_try!(get_trial_id(req.url()));
+        let study_id = http_try!(trial_id.get_study_id());
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+
+        let search_space = req.into_body();
+        let future = study_node.sample_params(trial_id, search_space);
+        Box::new(track_err!(future).then(into_http_response))
+    }
+}
+
+pub struct GetTrialShouldPrune(pub GlobalNodeHandle);
+impl HandleRequest for GetTrialShouldPrune {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/trials/*/should_prune";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<bool>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        should_prune(&self.0, req)
+    }
+}
+
+/// The same as `GetTrialShouldPrune` except that it requires the write permission.
+///
+/// Clients that restrict `GET` requests to reads should use this since
+/// the pruners write their bookkeeping attributes (e.g., rungs) to the trial.
+pub struct PostTrialShouldPrune(pub GlobalNodeHandle);
+impl HandleRequest for PostTrialShouldPrune {
+    const METHOD: &'static str = "POST";
+    const PATH: &'static str = "/trials/*/should_prune";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<bool>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        should_prune(&self.0, req)
+    }
+}
+
+fn should_prune(global: &GlobalNodeHandle, req: Req<()>) -> Reply<HttpResult<bool>> {
+    let trial_id = http_try!(get_trial_id(req.url()));
+    let study_id = http_try!(trial_id.get_study_id());
+    let study_node = http_try!(get_study_node(global, &study_id, &req));
+
+    let future = study_node.should_prune(trial_id);
+    Box::new(track_err!(future).then(into_http_response))
+}
+
 pub struct GetTrial(pub GlobalNodeHandle);
 impl HandleRequest for GetTrial {
     const METHOD: &'static str = "GET";
@@ -340,9 +1030,15 @@ impl HandleRequest for GetTrial {
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let trial_id = http_try!(get_trial_id(req.url()));
         let study_id = http_try!(trial_id.get_study_id());
-        let study_node = http_try!(self.0.get_study_node(&study_id));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
 
-        let future = study_node.get_trial(trial_id);
+        let format = http_try!(get_distribution_format(req.url()));
+        let future = study_node.get_trial(trial_id).map(move |mut trial| {
+            if let Some(format) = format {
+                trial.convert_distributions(format);
+            }
+            trial
+        });
         Box::new(track_err!(future).then(into_http_response))
     }
 }
@@ -360,12 +1056,158 @@ impl HandleRequest for GetTrials {
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let study_id = http_try!(get_study_id(req.url()));
-        let study_node = http_try!(self.0.get_study_node(&study_id));
-        let future = track_err!(study_node.get_trials());
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let format = http_try!(get_distribution_format(req.url()));
+        let future = track_err!(study_node.get_trials()).map(move |mut trials| {
+            if let Some(format) = format {
+                for trial in &mut trials {
+                    trial.convert_distributions(format);
+                }
+            }
+            trials
+        });
+        Box::new(future.then(into_http_response))
+    }
+}
+
+pub struct GetBestTrials(pub GlobalNodeHandle);
+impl HandleRequest for GetBestTrials {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/studies/*/best_trials";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<Vec<Trial>>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let k = http_try!(get_query_param(req.url(), "k")).unwrap_or(1);
+        let future = track_err!(study_node.get_best_trials(k));
+        Box::new(future.then(into_http_response))
+    }
+}
+
+pub struct GetStudyAudit(pub GlobalNodeHandle);
+impl HandleRequest for GetStudyAudit {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/studies/*/audit";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<Vec<study::Message>>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let trial_id = url_query_value(req.url(), "trial_id").map(TrialId::from);
+        let limit = http_try!(get_query_param(req.url(), "limit")).unwrap_or(100);
+        let future = track_err!(study_node.get_audit_log(trial_id, limit));
+        Box::new(future.then(into_http_response))
+    }
+}
+
+pub struct GetStudyStats(pub GlobalNodeHandle);
+impl HandleRequest for GetStudyStats {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/studies/*/stats";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<StudyStats>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let future = track_err!(study_node.get_stats());
+        Box::new(future.then(into_http_response))
+    }
+}
+
+pub struct GetLearningCurves(pub GlobalNodeHandle);
+impl HandleRequest for GetLearningCurves {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/studies/*/learning_curves";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<LearningCurves>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let states = http_try!(get_trial_states(req.url()));
+        let percentiles = http_try!(get_percentiles(req.url()))
+            .unwrap_or_else(|| learning_curve::DEFAULT_PERCENTILES.to_vec());
+        let future = track_err!(study_node.get_learning_curves(states, percentiles));
         Box::new(future.then(into_http_response))
     }
 }
 
+pub struct GetParamImportances(pub GlobalNodeHandle);
+impl HandleRequest for GetParamImportances {
+    const METHOD: &'static str = "GET";
+    const PATH: &'static str = "/studies/*/param_importances";
+
+    type ReqBody = ();
+    type ResBody = HttpResult<Vec<ParamImportance>>;
+    type Decoder = BodyDecoder<NullDecoder>;
+    type Encoder = BodyEncoder<JsonEncoder<Self::ResBody>>;
+    type Reply = Reply<Self::ResBody>;
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        let study_id = http_try!(get_study_id(req.url()));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
+        let future = study_node
+            .get_search_space(SearchSpaceMode::Intersection)
+            .join(study_node.get_trials())
+            .map(|(search_space, trials)| importance::evaluate(&search_space, &trials));
+        Box::new(track_err!(future).then(into_http_response))
+    }
+}
+
+/// Returns the handle of the study node `study_id` that records the client of `req` as the author of writes.
+fn get_study_node<T>(
+    global: &GlobalNodeHandle,
+    study_id: &StudyId,
+    req: &Req<T>,
+) -> Result<StudyNodeHandle> {
+    let study_node = track!(global.get_study_node(study_id))?;
+    let (client, verified) = get_client(global, req);
+    Ok(study_node.with_client(client, verified))
+}
+
+/// Returns the principal associated with the bearer token of `req` (if any).
+fn get_principal<T>(global: &GlobalNodeHandle, req: &Req<T>) -> Option<String> {
+    global
+        .auth_tokens()
+        .and_then(|tokens| tokens.principal(req.header().get_field("Authorization")))
+        .map(|principal| principal.to_owned())
+}
+
+/// Returns the identity of the client that issued `req` and whether it is verified.
+///
+/// The principal associated with the bearer token takes precedence over the `X-Client` header,
+/// which is unverified.
+fn get_client<T>(global: &GlobalNodeHandle, req: &Req<T>) -> (Option<String>, bool) {
+    if let Some(principal) = get_principal(global, req) {
+        return (Some(principal), true);
+    }
+    let client = req
+        .header()
+        .get_field(CLIENT_HEADER)
+        .map(|client| client.to_owned());
+    (client, false)
+}
+
 fn get_trial_id(url: &Url) -> Result<TrialId> {
     let id = url
         .path_segments()
@@ -381,7 +1223,7 @@ fn get_attr_key(url: &Url) -> Result<String> {
         .expect("never fails")
         .nth(3)
         .expect("never fails");
-    track!(percent_decode(key).map_err(Error::from))
+    track!(percent_decode(key))
 }
 
 fn get_subscribe_id(url: &Url) -> Result<SubscribeId> {
@@ -415,6 +1257,148 @@ fn get_study_id(url: &Url) -> Result<crate::study::StudyId> {
     Ok(crate::study::StudyId::from(id))
 }
 
+fn get_query_param<T>(url: &Url, key: &str) -> Result<Option<T>>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    if let Some((_, v)) = url.query_pairs().find(|(k, _)| k == key) {
+        let v = track!(v
+            .parse()
+            .map_err(|e| Error::from(ErrorKind::InvalidInput.cause(e))); key, v)?;
+        Ok(Some(v))
+    } else {
+        Ok(None)
+    }
+}
+
+fn url_query_value(url: &Url, key: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+}
+
+/// Parses tag filters (e.g., `?tag=project:foo&tag=owner`).
+///
+/// A filter without `:` only requires the study to have the tag.
+fn get_tag_filters(url: &Url) -> Result<Vec<(String, Option<String>)>> {
+    let mut filters = Vec::new();
+    for (_, v) in url.query_pairs().filter(|(k, _)| k == "tag") {
+        track_assert!(!v.is_empty(), ErrorKind::InvalidInput, "Empty tag filter");
+        let mut tokens = v.splitn(2, ':');
+        let key = tokens.next().expect("never fails").to_owned();
+        let value = tokens.next().map(|v| v.to_owned());
+        filters.push((key, value));
+    }
+    Ok(filters)
+}
+
+/// Parses a comma separated list of trial states (e.g., `?states=COMPLETE,PRUNED`).
+fn get_trial_states(url: &Url) -> Result<Option<Vec<TrialState>>> {
+    let value = url
+        .query_pairs()
+        .find(|(k, _)| k == "states")
+        .map(|(_, v)| v);
+    if let Some(value) = value {
+        let states = value
+            .split(',')
+            .map(|s| {
+                track!(serde_json::from_value(JsonValue::String(s.to_owned()))
+                    .map_err(|e| Error::from(ErrorKind::InvalidInput.cause(e))); s)
+            })
+            .collect::<Result<_>>();
+        Ok(Some(track!(states)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Parses a comma separated list of percentiles (e.g., `?percentiles=10,90`).
+fn get_percentiles(url: &Url) -> Result<Option<Vec<f64>>> {
+    let value = url
+        .query_pairs()
+        .find(|(k, _)| k == "percentiles")
+        .map(|(_, v)| v);
+    if let Some(value) = value {
+        let mut percentiles = Vec::new();
+        for s in value.split(',').filter(|s| !s.is_empty()) {
+            let p: f64 = track!(s
+                .parse()
+                .map_err(|e| Error::from(ErrorKind::InvalidInput.cause(e))); s)?;
+            track_assert!(
+                (0.0..=100.0).contains(&p),
+                ErrorKind::InvalidInput,
+                "Percentile must be in the range [0, 100]: {}",
+                p
+            );
+            percentiles.push(p);
+        }
+        Ok(Some(percentiles))
+    } else {
+        Ok(None)
+    }
+}
+
+fn get_distribution_format(url: &Url) -> Result<Option<DistributionFormat>> {
+    let value = url
+        .query_pairs()
+        .find(|(k, _)| k == "distribution_format")
+        .map(|(_, v)| v);
+    match value.as_ref().map(|v| v.as_ref()) {
+        None => Ok(None),
+        Some("legacy") => Ok(Some(DistributionFormat::Legacy)),
+        Some("modern") => Ok(Some(DistributionFormat::Modern)),
+        Some(v) => track_panic!(
+            ErrorKind::InvalidInput,
+            "Unknown distribution format: {:?}",
+            v
+        ),
+    }
+}
+
+fn get_search_space_mode(url: &Url) -> Result<SearchSpaceMode> {
+    let value = url.query_pairs().find(|(k, _)| k == "mode").map(|(_, v)| v);
+    match value.as_ref().map(|v| v.as_ref()) {
+        None | Some("intersection") => Ok(SearchSpaceMode::Intersection),
+        Some("union") => Ok(SearchSpaceMode::Union),
+        Some(v) => track_panic!(
+            ErrorKind::InvalidInput,
+            "Unknown search space mode: {:?}",
+            v
+        ),
+    }
+}
+
+/// Returns the namespace specified by the `X-Plumtuna-Namespace` header, or the default one.
+fn get_namespace<T>(req: &Req<T>) -> Result<Namespace> {
+    if let Some(namespace) = req.header().get_field(NAMESPACE_HEADER) {
+        track!(validate_namespace(namespace))?;
+        Ok(Namespace::new(namespace.to_owned()))
+    } else {
+        Ok(Namespace::default())
+    }
+}
+
+fn get_namespace_from_path(url: &Url) -> Result<Namespace> {
+    let namespace = url
+        .path_segments()
+        .expect("never fails")
+        .nth(1)
+        .expect("never fails");
+    let namespace = track!(percent_decode(namespace))?;
+    track!(validate_namespace(&namespace))?;
+    Ok(Namespace::new(namespace))
+}
+
+fn validate_namespace(namespace: &str) -> Result<()> {
+    track_assert!(
+        !namespace.is_empty(),
+        ErrorKind::InvalidInput,
+        "Empty namespace"
+    );
+    Ok(())
+}
+
 fn get_study_name(url: &Url) -> Result<StudyName> {
     let name = url
         .path_segments()
@@ -429,11 +1413,59 @@ pub struct PostStudyReq {
     study_name: study::StudyName,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostStudyWarmStartReq {
+    source_study_ids: Vec<study::StudyId>,
+    #[serde(default)]
+    value_threshold: Option<f64>,
+    #[serde(default)]
+    top_k: Option<usize>,
+    #[serde(default)]
+    min_param_overlap: Option<f64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PostStudyRes {
     study_id: study::StudyId,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NamespaceEntry {
+    namespace: Namespace,
+    n_studies: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClusterStats {
+    rejected_messages: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StudyListEntry {
+    namespace: Namespace,
+    study_name: StudyName,
+    study_id: study::StudyId,
+    direction: StudyDirection,
+    state: StudyState,
+    tags: BTreeMap<String, String>,
+    n_trials: u32,
+    best_value: Option<f64>,
+}
+impl From<StudySummary> for StudyListEntry {
+    fn from(f: StudySummary) -> Self {
+        StudyListEntry {
+            namespace: f.namespace,
+            study_name: f.study_name,
+            study_id: f.study_id,
+            direction: f.direction,
+            state: f.state,
+            tags: f.tags,
+            n_trials: f.n_trials,
+            best_value: f.best_trial.and_then(|t| t.value),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum HttpResult<T> {
@@ -450,20 +1482,145 @@ fn into_http_response<T>(
 ) -> std::result::Result<Res<HttpResult<T>>, Never> {
     Ok(match result {
         Ok(v) => Res::new(Status::Ok, HttpResult::Ok(v)),
-        Err(e) => {
-            let status = match *e.kind() {
-                ErrorKind::AlreadyExists => Status::Conflict,
-                ErrorKind::NotFound => Status::NotFound,
-                ErrorKind::Other => Status::InternalServerError,
+        Err(e) => error_response(e),
+    })
+}
+
+fn error_response<T>(e: Error) -> Res<HttpResult<T>> {
+    let body = HttpResult::Err {
+        reason: e.to_string(),
+    };
+    let status = match *e.kind() {
+        ErrorKind::AlreadyExists => Status::Conflict,
+        ErrorKind::NotFound => Status::NotFound,
+        ErrorKind::InvalidInput => Status::BadRequest,
+        ErrorKind::LimitExceeded => Status::Conflict,
+        ErrorKind::Unauthenticated => Status::Unauthorized,
+        ErrorKind::PermissionDenied => Status::Forbidden,
+        ErrorKind::RateLimited => return too_many_requests(body),
+        ErrorKind::InvalidState => Status::Conflict,
+        ErrorKind::Other => Status::InternalServerError,
+    };
+    let mut res = Res::new(status, body);
+    if status == Status::Unauthorized {
+        let field = HeaderField::new("WWW-Authenticate", "Bearer").expect("never fails");
+        res.header_mut().add_field(field);
+    }
+    res
+}
+
+/// Makes a `429 Too Many Requests` response.
+///
+/// `fibers_http_server::Status` has no variant for this status code, so the response is built directly.
+fn too_many_requests<T>(body: T) -> Res<T> {
+    let status = StatusCode::new(429).expect("never fails");
+    let reason = ReasonPhrase::new("Too Many Requests").expect("never fails");
+    Res::from(Response::new(HttpVersion::V1_1, status, reason, body))
+}
+
+/// A handler wrapper that authenticates requests by bearer tokens,
+/// authorizes them by the access control list of the target study, and
+/// applies the rate limits of the global node to write (i.e., non-`GET`) requests.
+///
+/// Write requests are also rejected while too many messages of the study are waiting for the broadcast limit.
+///
+/// `GET` requests require the read-only scope and the others require the read-write scope.
+/// If the global node has no token store, every request passes the authentication without any principal.
+pub struct Authenticated<H> {
+    inner: H,
+    global: GlobalNodeHandle,
+}
+impl<H: HandleRequest> Authenticated<H> {
+    pub fn new(inner: H, global: GlobalNodeHandle) -> Self {
+        Authenticated { inner, global }
+    }
+
+    fn authorize(&self, req: &Req<()>) -> Result<()> {
+        let header = req.header();
+        let principal = if let Some(tokens) = self.global.auth_tokens() {
+            let scope = if H::METHOD == "GET" {
+                Scope::ReadOnly
+            } else {
+                Scope::ReadWrite
             };
-            Res::new(
-                status,
-                HttpResult::Err {
-                    reason: e.to_string(),
-                },
-            )
+            track!(tokens.authorize(header.get_field("Authorization"), scope))?
+        } else {
+            None
+        };
+
+        let study_id = if H::PATH.starts_with("/studies/*") {
+            Some(track!(get_study_id(req.url()))?)
+        } else if H::PATH.starts_with("/trials/*") {
+            Some(track!(track!(get_trial_id(req.url()))?.get_study_id())?)
+        } else {
+            None
+        };
+        if let Some(study_id) = &study_id {
+            let permission = if H::PATH.starts_with("/studies/*/acl")
+                || H::PATH.starts_with("/studies/*/webhooks")
+            {
+                Permission::Admin
+            } else if H::METHOD == "GET" || H::PATH == "/studies/*/clone" {
+                Permission::Read
+            } else {
+                Permission::Write
+            };
+            if let Ok(study_node) = self.global.get_study_node(study_id) {
+                track!(study_node.authorize(principal, permission))?;
+                let modifies = H::METHOD != "GET" && permission != Permission::Read;
+                if modifies && !STATE_INDEPENDENT_PATHS.contains(&H::PATH) {
+                    track!(study_node.check_writable())?;
+                }
+                if H::METHOD != "GET" {
+                    track!(study_node.check_pending_broadcasts())?;
+                }
+            }
         }
-    })
+
+        // Checked last so that unauthorized requests do not consume the quota of others.
+        if H::METHOD != "GET" {
+            let (client, verified) = get_client(&self.global, req);
+            track!(self.global.rate_limiter().check(
+                study_id.as_ref(),
+                client.as_deref(),
+                verified
+            ))?;
+        }
+        Ok(())
+    }
+}
+impl<H, T> HandleRequest for Authenticated<H>
+where
+    H: HandleRequest<ResBody = HttpResult<T>>,
+    T: Send + 'static,
+{
+    const METHOD: &'static str = H::METHOD;
+    const PATH: &'static str = H::PATH;
+
+    type ReqBody = H::ReqBody;
+    type ResBody = H::ResBody;
+    type Decoder = H::Decoder;
+    type Encoder = H::Encoder;
+    type Reply = H::Reply;
+
+    fn handle_request_head(&self, req: &Req<()>) -> Option<Res<Self::ResBody>> {
+        if let Err(e) = track!(self.authorize(req)) {
+            return Some(error_response(e));
+        }
+        self.inner.handle_request_head(req)
+    }
+
+    fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
+        self.inner.handle_request(req)
+    }
+
+    fn handle_decoding_error(
+        &self,
+        req: Req<()>,
+        error: &fibers_http_server::Error,
+    ) -> Option<Res<Self::ResBody>> {
+        self.inner.handle_decoding_error(req, error)
+    }
 }
 
 pub struct PostStudySubscribe(pub GlobalNodeHandle);
@@ -479,7 +1636,7 @@ impl HandleRequest for PostStudySubscribe {
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let study_id = http_try!(get_study_id(req.url()));
-        let study_node = http_try!(self.0.get_study_node(&study_id));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
         let future = track_err!(study_node.subscribe());
         Box::new(future.then(into_http_response))
     }
@@ -498,7 +1655,7 @@ impl HandleRequest for GetNewEvents {
 
     fn handle_request(&self, req: Req<Self::ReqBody>) -> Self::Reply {
         let study_id = http_try!(get_study_id(req.url()));
-        let study_node = http_try!(self.0.get_study_node(&study_id));
+        let study_node = http_try!(get_study_node(&self.0, &study_id, &req));
         let subscribe_id = http_try!(get_subscribe_id(req.url()));
         let future = track_err!(study_node.poll_events(subscribe_id));
         Box::new(future.then(into_http_response))
@@ -0,0 +1,274 @@
+use crate::distribution::Distribution;
+use crate::search_space::SearchSpace;
+use crate::trial::Trial;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const N_TREES: usize = 64;
+const MAX_DEPTH: usize = 64;
+const SEED: u64 = 0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParamImportance {
+    pub name: String,
+    pub importance: f64,
+}
+
+/// Evaluates the importance of each parameter in `search_space` from the completed `trials`.
+///
+/// The importance is the mean decrease impurity of a random forest fitted to the trials.
+/// Categorical parameters are one-hot encoded (so that splits do not depend on the order of the choices),
+/// and the importance of such a parameter is the sum of those of its columns.
+/// The importances are normalized so that they sum up to `1.0` and are sorted in descending order.
+pub fn evaluate(search_space: &SearchSpace, trials: &[Trial]) -> Vec<ParamImportance> {
+    let distributions = search_space
+        .values()
+        .map(|d| d.to_modern())
+        .collect::<Vec<_>>();
+
+    // The index of the parameter that each column belongs to.
+    let owners = distributions
+        .iter()
+        .enumerate()
+        .flat_map(|(i, distribution)| {
+            let n_columns = match distribution {
+                Distribution::Categorical { choices } => choices.len(),
+                _ => 1,
+            };
+            std::iter::repeat_n(i, n_columns)
+        })
+        .collect::<Vec<_>>();
+
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    for trial in trials.iter().filter(|t| t.is_complete()) {
+        let y = match trial.value {
+            Some(y) if y.is_finite() => y,
+            _ => continue,
+        };
+        let mut x = Vec::with_capacity(owners.len());
+        for (name, distribution) in search_space.keys().zip(&distributions) {
+            let value = match trial.params.get(name) {
+                None => break,
+                Some(p) => p.value,
+            };
+            match distribution {
+                Distribution::Categorical { choices } => {
+                    x.extend((0..choices.len()).map(|j| if value == j as f64 { 1.0 } else { 0.0 }));
+                }
+                _ if is_log_scale(distribution) => x.push(value.ln()),
+                _ => x.push(value),
+            }
+        }
+        if x.len() == owners.len() {
+            xs.push(x);
+            ys.push(y);
+        }
+    }
+
+    let mut importances = vec![0.0; search_space.len()];
+    if !xs.is_empty() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        for _ in 0..N_TREES {
+            let samples = (0..xs.len())
+                .map(|_| rng.gen_range(0, xs.len()))
+                .collect::<Vec<_>>();
+            let mut tree = Tree {
+                xs: &xs,
+                ys: &ys,
+                importances: vec![0.0; owners.len()],
+            };
+            tree.grow(samples, 0);
+            let total = tree.importances.iter().sum::<f64>();
+            if total > 0.0 {
+                for (&owner, b) in owners.iter().zip(tree.importances) {
+                    importances[owner] += b / total;
+                }
+            }
+        }
+    }
+
+    let total = importances.iter().sum::<f64>();
+    let mut result = search_space
+        .keys()
+        .zip(importances)
+        .map(|(name, importance)| ParamImportance {
+            name: name.clone(),
+            importance: if total > 0.0 { importance / total } else { 0.0 },
+        })
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| {
+        b.importance
+            .partial_cmp(&a.importance)
+            .expect("never fails")
+    });
+    result
+}
+
+fn is_log_scale(distribution: &Distribution) -> bool {
+    match *distribution {
+        Distribution::Float { log, .. } | Distribution::Int { log, .. } => log,
+        _ => false,
+    }
+}
+
+/// A regression tree that only records the impurity decrease of each feature.
+#[derive(Debug)]
+struct Tree<'a> {
+    xs: &'a [Vec<f64>],
+    ys: &'a [f64],
+    importances: Vec<f64>,
+}
+impl<'a> Tree<'a> {
+    fn grow(&mut self, samples: Vec<usize>, depth: usize) {
+        if samples.len() < 2 || depth >= MAX_DEPTH {
+            return;
+        }
+
+        let mut best: Option<(usize, f64, f64)> = None; // (feature, threshold, decrease)
+        let parent_sse = sse(samples.iter().map(|&i| self.ys[i]));
+        for feature in 0..self.importances.len() {
+            let mut sorted = samples.clone();
+            sorted.sort_by(|&a, &b| {
+                self.xs[a][feature]
+                    .partial_cmp(&self.xs[b][feature])
+                    .expect("never fails")
+            });
+
+            let total_sum = sorted.iter().map(|&i| self.ys[i]).sum::<f64>();
+            let total_sq = sorted.iter().map(|&i| self.ys[i].powi(2)).sum::<f64>();
+            let (mut left_sum, mut left_sq) = (0.0, 0.0);
+            for k in 1..sorted.len() {
+                let y = self.ys[sorted[k - 1]];
+                left_sum += y;
+                left_sq += y * y;
+
+                let (lower, upper) = (self.xs[sorted[k - 1]][feature], self.xs[sorted[k]][feature]);
+                if lower == upper {
+                    continue;
+                }
+                let (n_left, n_right) = (k as f64, (sorted.len() - k) as f64);
+                let left_sse = left_sq - left_sum * left_sum / n_left;
+                let right_sse = (total_sq - left_sq) - (total_sum - left_sum).powi(2) / n_right;
+                let decrease = parent_sse - left_sse - right_sse;
+                if best.is_none_or(|b| decrease > b.2) {
+                    best = Some((feature, (lower + upper) / 2.0, decrease));
+                }
+            }
+        }
+
+        if let Some((feature, threshold, decrease)) = best {
+            if decrease <= 0.0 {
+                return;
+            }
+            self.importances[feature] += decrease;
+            let (left, right) = samples
+                .into_iter()
+                .partition(|&i| self.xs[i][feature] <= threshold);
+            self.grow(left, depth + 1);
+            self.grow(right, depth + 1);
+        }
+    }
+}
+
+fn sse<I>(ys: I) -> f64
+where
+    I: Iterator<Item = f64> + Clone,
+{
+    let n = ys.clone().count() as f64;
+    let mean = ys.clone().sum::<f64>() / n;
+    ys.map(|y| (y - mean).powi(2)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distribution::Category;
+    use crate::study::StudyId;
+    use crate::time::Timestamp;
+    use crate::trial::{TrialId, TrialParamValue, TrialState};
+
+    fn search_space() -> SearchSpace {
+        let mut search_space = SearchSpace::new();
+        search_space.insert(
+            "c".to_owned(),
+            Distribution::Categorical {
+                choices: vec![
+                    Category::Str("a".to_owned()),
+                    Category::Str("b".to_owned()),
+                    Category::Str("c".to_owned()),
+                ],
+            },
+        );
+        search_space.insert(
+            "x".to_owned(),
+            Distribution::Float {
+                low: 0.0,
+                high: 1.0,
+                log: false,
+                step: None,
+            },
+        );
+        search_space
+    }
+
+    fn trial(search_space: &SearchSpace, c: f64, x: f64, value: f64) -> Trial {
+        let mut trial = Trial::new(TrialId::new(&StudyId::new()));
+        for (name, v) in [("c", c), ("x", x)] {
+            let param = TrialParamValue::new(v, search_space[name].clone());
+            trial.params.insert(name.to_owned(), param);
+        }
+        trial.value = Some(value);
+        trial.set_state(TrialState::Complete, Timestamp::now());
+        trial
+    }
+
+    #[test]
+    fn importances_are_normalized() {
+        let search_space = search_space();
+        let trials = (0..30)
+            .map(|i| {
+                let x = f64::from(i) / 30.0;
+                trial(&search_space, f64::from(i % 3), x, x * 10.0)
+            })
+            .collect::<Vec<_>>();
+
+        let importances = evaluate(&search_space, &trials);
+        assert_eq!(importances.len(), 2);
+        assert_eq!(importances[0].name, "x");
+        assert!(importances[0].importance >= importances[1].importance);
+        let total = importances.iter().map(|p| p.importance).sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn categorical_params_are_not_ordered() {
+        // Only the middle choice matters, which an ordered split cannot isolate with a single threshold.
+        let search_space = search_space();
+        let trials = (0..30)
+            .map(|i| {
+                let c = f64::from(i % 3);
+                let x = f64::from((i * 7) % 30) / 30.0;
+                let value = if c == 1.0 { 10.0 } else { 0.0 } + x * 0.1;
+                trial(&search_space, c, x, value)
+            })
+            .collect::<Vec<_>>();
+
+        let importances = evaluate(&search_space, &trials);
+        assert_eq!(importances[0].name, "c");
+        assert!(importances[0].importance > 0.9);
+    }
+
+    #[test]
+    fn no_trials() {
+        let importances = evaluate(&search_space(), &[]);
+        assert!(importances.iter().all(|p| p.importance == 0.0));
+
+        // Trials that lack some of the parameters are ignored.
+        let search_space = search_space();
+        let mut incomplete = trial(&search_space, 0.0, 0.5, 1.0);
+        incomplete.params.remove("x");
+        let importances = evaluate(&search_space, &[incomplete]);
+        assert!(importances.iter().all(|p| p.importance == 0.0));
+    }
+}
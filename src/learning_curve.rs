@@ -0,0 +1,174 @@
+use crate::pruner::percentile_of;
+use crate::study::StudyDirection;
+use crate::trial::{Trial, TrialId};
+use std::collections::BTreeMap;
+
+pub const DEFAULT_PERCENTILES: [f64; 2] = [25.0, 75.0];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearningCurves {
+    /// The percentiles reported in `StepSummary::percentiles` (in the same order).
+    pub percentiles: Vec<f64>,
+    pub steps: Vec<StepSummary>,
+    pub best_so_far: Vec<TrialCurve>,
+}
+
+/// Aggregation of the intermediate values reported at a step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepSummary {
+    pub step: u32,
+    pub n_trials: usize,
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+    pub percentiles: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrialCurve {
+    pub trial_id: TrialId,
+    pub values: BTreeMap<u32, f64>,
+}
+
+/// Aggregates the intermediate values of `trials` step by step.
+///
+/// `NaN` values are ignored.
+/// The best-so-far curve of each trial follows `direction` (minimization unless `Maximize`).
+pub fn aggregate<'a, I>(direction: StudyDirection, trials: I, percentiles: &[f64]) -> LearningCurves
+where
+    I: Iterator<Item = &'a Trial>,
+{
+    let mut step_values = BTreeMap::<u32, Vec<f64>>::new();
+    let mut best_so_far = Vec::new();
+    for trial in trials {
+        let mut best = None;
+        let mut curve = BTreeMap::new();
+        for (&step, &value) in trial.intermediate_values.iter().filter(|x| !x.1.is_nan()) {
+            step_values.entry(step).or_default().push(value);
+            best = Some(match best {
+                None => value,
+                Some(b) if direction == StudyDirection::Maximize => value.max(b),
+                Some(b) => value.min(b),
+            });
+            curve.insert(step, best.expect("never fails"));
+        }
+        if !curve.is_empty() {
+            best_so_far.push(TrialCurve {
+                trial_id: trial.trial_id.clone(),
+                values: curve,
+            });
+        }
+    }
+    best_so_far.sort_by(|a, b| a.trial_id.cmp(&b.trial_id));
+
+    let steps = step_values
+        .into_iter()
+        .map(|(step, mut values)| StepSummary {
+            step,
+            n_trials: values.len(),
+            median: percentile_of(&mut values, 50.0).expect("never fails"),
+            min: values[0],
+            max: values[values.len() - 1],
+            percentiles: percentiles
+                .iter()
+                .map(|&p| percentile_of(&mut values, p).expect("never fails"))
+                .collect(),
+        })
+        .collect();
+    LearningCurves {
+        percentiles: percentiles.to_owned(),
+        steps,
+        best_so_far,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::study::StudyId;
+
+    fn trial(study_id: &StudyId, values: &[(u32, f64)]) -> Trial {
+        let mut trial = Trial::new(TrialId::new(study_id));
+        trial.intermediate_values.extend(values.iter().cloned());
+        trial
+    }
+
+    fn trials() -> Vec<Trial> {
+        let study_id = StudyId::new();
+        vec![
+            trial(&study_id, &[(0, 3.0), (1, 1.0), (2, 2.0)]),
+            trial(&study_id, &[(0, 1.0), (1, f64::NAN), (2, 4.0)]),
+            trial(&study_id, &[(0, 2.0), (2, 0.0)]),
+            trial(&study_id, &[(0, 4.0)]),
+            trial(&study_id, &[(5, f64::NAN)]),
+        ]
+    }
+
+    #[test]
+    fn steps_are_summarized() {
+        let trials = trials();
+        let curves = aggregate(
+            StudyDirection::Minimize,
+            trials.iter(),
+            &DEFAULT_PERCENTILES,
+        );
+        assert_eq!(curves.percentiles, DEFAULT_PERCENTILES);
+
+        // Steps that only have NaN values are omitted.
+        let steps = curves.steps.iter().map(|s| s.step).collect::<Vec<_>>();
+        assert_eq!(steps, [0, 1, 2]);
+
+        let s = &curves.steps[0];
+        assert_eq!(s.n_trials, 4);
+        assert_eq!((s.min, s.median, s.max), (1.0, 2.5, 4.0));
+        assert_eq!(s.percentiles, [1.75, 3.25]);
+
+        let s = &curves.steps[1];
+        assert_eq!(s.n_trials, 1);
+        assert_eq!((s.min, s.median, s.max), (1.0, 1.0, 1.0));
+
+        let s = &curves.steps[2];
+        assert_eq!(s.n_trials, 3);
+        assert_eq!((s.min, s.median, s.max), (0.0, 2.0, 4.0));
+        assert_eq!(s.percentiles, [1.0, 3.0]);
+    }
+
+    #[test]
+    fn best_so_far_follows_direction() {
+        let trials = trials();
+        let curve_of = |curves: &LearningCurves, trial: &Trial| {
+            curves
+                .best_so_far
+                .iter()
+                .find(|c| c.trial_id == trial.trial_id)
+                .map(|c| c.values.iter().map(|(&s, &v)| (s, v)).collect::<Vec<_>>())
+        };
+
+        let curves = aggregate(StudyDirection::Minimize, trials.iter(), &[]);
+        assert_eq!(curves.best_so_far.len(), 4);
+        assert!(curves
+            .best_so_far
+            .windows(2)
+            .all(|w| w[0].trial_id < w[1].trial_id));
+        assert_eq!(
+            curve_of(&curves, &trials[0]),
+            Some(vec![(0, 3.0), (1, 1.0), (2, 1.0)])
+        );
+        assert_eq!(
+            curve_of(&curves, &trials[1]),
+            Some(vec![(0, 1.0), (2, 1.0)])
+        );
+        assert_eq!(curve_of(&curves, &trials[4]), None);
+
+        let curves = aggregate(StudyDirection::Maximize, trials.iter(), &[]);
+        assert_eq!(
+            curve_of(&curves, &trials[0]),
+            Some(vec![(0, 3.0), (1, 3.0), (2, 3.0)])
+        );
+        assert_eq!(
+            curve_of(&curves, &trials[1]),
+            Some(vec![(0, 1.0), (2, 4.0)])
+        );
+        assert!(curves.steps[0].percentiles.is_empty());
+    }
+}
@@ -5,15 +5,27 @@ extern crate trackable;
 
 pub use self::error::{Error, ErrorKind};
 
+pub mod auth;
+pub mod cluster_key;
 pub mod contact;
 pub mod distribution;
 pub mod global;
 pub mod http;
+pub mod importance;
+pub mod learning_curve;
+pub mod pruner;
+pub mod rate_limit;
+pub mod sampler;
+pub mod search_space;
 pub mod study;
 pub mod time;
+pub mod tls;
 pub mod trial;
+pub mod warm_start;
+pub mod webhook;
 
 mod error;
+mod hash;
 mod message;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -3,9 +3,16 @@ use fibers_http_server::ServerBuilder;
 use futures::Future;
 use plumcast::node::{NodeBuilder, UnixtimeLocalNodeIdGenerator};
 use plumcast::service::ServiceBuilder;
+use plumtuna::auth::TokenStore;
+use plumtuna::cluster_key::{ClusterKey, RandomLocalNodeIdGenerator};
 use plumtuna::contact::{ContactService, ContactServiceClient};
 use plumtuna::global::GlobalNodeBuilder;
-use std::net::{SocketAddr, ToSocketAddrs};
+use plumtuna::http::Authenticated;
+use plumtuna::rate_limit::{RateLimit, RateLimiter};
+use plumtuna::study::StudyLifetime;
+use plumtuna::tls::{TlsOptions, TlsTerminator};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::path::PathBuf;
 use trackable::result::MainResult;
 use trackable::{track, track_any_err};
 
@@ -18,6 +25,26 @@ struct Opt {
     #[clap(long, default_value = "7363")]
     http_port: u16,
 
+    /// Address on which the HTTP API listens.
+    #[clap(long, default_value = "0.0.0.0")]
+    http_bind_addr: IpAddr,
+
+    /// PEM file of the certificate chain used to serve the HTTP API over HTTPS.
+    #[clap(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM file of the private key corresponding to `--tls-cert`.
+    #[clap(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// PEM file of the CA certificates used to verify client certificates.
+    ///
+    /// If given, HTTPS clients must present a certificate signed by one of them.
+    /// Note that the plain HTTP server behind the TLS terminator listens on an ephemeral loopback port,
+    /// so local processes can still reach the API without a certificate.
+    #[clap(long, requires = "tls_cert")]
+    tls_client_ca: Option<PathBuf>,
+
     #[clap(long, default_value = "127.0.0.1:7364")]
     rpc_addr: SocketAddr,
 
@@ -26,6 +53,55 @@ struct Opt {
 
     #[clap(long, default_value = "1")]
     threads: usize,
+
+    /// Maximum number of studies that can be created in a namespace.
+    #[clap(long)]
+    max_studies_per_namespace: Option<usize>,
+
+    /// File of bearer tokens accepted by the HTTP API (each line is `<token> <read-only|read-write> [<principal>]`).
+    ///
+    /// If omitted, the HTTP API accepts any request.
+    #[clap(long)]
+    auth_tokens_file: Option<PathBuf>,
+
+    /// File of the secret key shared by all nodes in the cluster.
+    ///
+    /// If given, the RPC and gossip payloads between nodes are authenticated (but not encrypted) by HMAC-SHA256 with this key,
+    /// and the ones that fail verification, are older than five minutes or are replayed are dropped.
+    /// The nodes also use random IDs that can only be learned from authenticated payloads,
+    /// so processes without the key cannot join the membership of plumcast.
+    /// Payloads are still readable on the network, so the RPC address should be reachable only from trusted hosts.
+    #[clap(long)]
+    cluster_key_file: Option<PathBuf>,
+
+    /// Token bucket limit of write requests per study (`<RATE>[:<BURST>]`, e.g., `100:200`).
+    ///
+    /// Requests exceeding the limit are rejected with `429 Too Many Requests`.
+    #[clap(long)]
+    rate_limit_per_study: Option<RateLimit>,
+
+    /// Token bucket limit of write requests per client (`<RATE>[:<BURST>]`).
+    ///
+    /// Clients are identified by the principals of their tokens or the `X-Client` header.
+    /// The latter is not verified, so it only limits cooperative clients
+    /// (and never consumes the tokens of the principals).
+    #[clap(long)]
+    rate_limit_per_client: Option<RateLimit>,
+
+    /// Token bucket limit of broadcasts issued by each study node (`<RATE>[:<BURST>]`).
+    ///
+    /// Messages exceeding the limit are delayed,
+    /// and write requests to a study are rejected with 429 while too many of its messages are delayed.
+    #[clap(long)]
+    broadcast_limit: Option<RateLimit>,
+
+    /// Default number of seconds for which an idle study is kept on this node (or `never`).
+    ///
+    /// Only writes and `POST /studies/{id}/keepalive` reset the idle time.
+    ///
+    /// It can be overridden per study via `PUT /studies/{id}/lifetime`.
+    #[clap(long, default_value = "3600")]
+    study_lifetime: StudyLifetime,
 }
 
 fn main() -> MainResult {
@@ -33,21 +109,54 @@ fn main() -> MainResult {
 
     let opt = Opt::parse();
     fibers_global::set_thread_count(opt.threads);
+    let cluster_key = if let Some(path) = &opt.cluster_key_file {
+        Some(track!(ClusterKey::load(path))?)
+    } else {
+        None
+    };
 
     let mut service_builder = ServiceBuilder::new(opt.rpc_addr);
 
-    let contact_service = ContactService::new(service_builder.rpc_server_builder_mut());
+    let contact_service = ContactService::new(
+        service_builder.rpc_server_builder_mut(),
+        cluster_key.clone(),
+    );
 
-    let global_node_builder = GlobalNodeBuilder::new(service_builder.rpc_server_builder_mut());
+    let mut global_node_builder = GlobalNodeBuilder::new(
+        service_builder.rpc_server_builder_mut(),
+        cluster_key.clone(),
+    );
+    if let Some(n) = opt.max_studies_per_namespace {
+        global_node_builder.max_studies_per_namespace(n);
+    }
+    if let Some(path) = &opt.auth_tokens_file {
+        global_node_builder.auth_tokens(track!(TokenStore::load(path))?);
+    }
+    let mut rate_limiter = RateLimiter::new();
+    if let Some(limit) = opt.rate_limit_per_study {
+        rate_limiter.per_study(limit);
+    }
+    if let Some(limit) = opt.rate_limit_per_client {
+        rate_limiter.per_client(limit);
+    }
+    global_node_builder.rate_limiter(rate_limiter);
+    if let Some(limit) = opt.broadcast_limit {
+        global_node_builder.broadcast_limit(limit);
+    }
+    global_node_builder.study_lifetime(opt.study_lifetime);
 
-    let service =
-        service_builder.finish(fibers_global::handle(), UnixtimeLocalNodeIdGenerator::new());
+    let service = if cluster_key.is_some() {
+        service_builder.finish(fibers_global::handle(), RandomLocalNodeIdGenerator)
+    } else {
+        service_builder.finish(fibers_global::handle(), UnixtimeLocalNodeIdGenerator::new())
+    };
     let mut node = NodeBuilder::new().finish(service.handle());
     let plumcast_service_handle = service.handle();
     contact_service.handle().set_contact_node_id(node.id());
 
     let rpc_client_service_handle = service.rpc_client_service().handle();
-    let contact_service_client = ContactServiceClient::new(service.rpc_client_service().handle());
+    let contact_service_client =
+        ContactServiceClient::new(service.rpc_client_service().handle(), cluster_key);
     fibers_global::spawn(service.map_err(|e| panic!("{}", e)));
     fibers_global::spawn(contact_service.map_err(|e| panic!("{}", e)));
 
@@ -78,29 +187,88 @@ fn main() -> MainResult {
     );
     let handle = global_node.handle();
 
-    let mut builder = ServerBuilder::new(([0, 0, 0, 0], opt.http_port).into());
-
-    track!(builder.add_handler(plumtuna::http::PostStudy(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::GetStudyByName(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::GetStudies(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::GetStudy(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PutStudyDirection(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PutStudySystemAttr(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PutStudyUserAttr(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PostStudySubscribe(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::GetNewEvents(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PostTrial(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PutTrialState(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PutTrialParam(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PutTrialValue(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PutTrialIntermediateValue(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PutTrialSystemAttr(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::PutTrialUserAttr(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::GetTrial(handle.clone())))?;
-    track!(builder.add_handler(plumtuna::http::GetTrials(handle.clone())))?;
+    let http_addr = SocketAddr::new(opt.http_bind_addr, opt.http_port);
+    let tls_options = match (opt.tls_cert, opt.tls_key) {
+        (Some(cert), Some(key)) => Some(TlsOptions {
+            cert,
+            key,
+            client_ca: opt.tls_client_ca,
+        }),
+        _ => None,
+    };
+    let mut builder = if tls_options.is_some() {
+        // Only the TLS terminator can reach the plain HTTP server.
+        ServerBuilder::new(([127, 0, 0, 1], 0).into())
+    } else {
+        ServerBuilder::new(http_addr)
+    };
+    macro_rules! add_handler {
+        ($handler:ident) => {
+            track!(builder.add_handler(Authenticated::new(
+                plumtuna::http::$handler(handle.clone()),
+                handle.clone()
+            )))?;
+        };
+    }
+
+    add_handler!(PostStudy);
+    add_handler!(PostStudyClone);
+    add_handler!(PostStudyWarmStart);
+    add_handler!(GetStudyByName);
+    add_handler!(GetStudies);
+    add_handler!(GetNamespaces);
+    add_handler!(GetNamespaceStudies);
+    add_handler!(GetClusterStats);
+    add_handler!(GetStudy);
+    add_handler!(PutStudyDirection);
+    add_handler!(PutStudySystemAttr);
+    add_handler!(PutStudyTag);
+    add_handler!(DeleteStudyTag);
+    add_handler!(GetStudyAcl);
+    add_handler!(PutStudyAcl);
+    add_handler!(DeleteStudyAcl);
+    add_handler!(GetStudyWebhooks);
+    add_handler!(PutStudyWebhook);
+    add_handler!(DeleteStudyWebhook);
+    add_handler!(PutStudyUserAttr);
+    add_handler!(PutStudySampler);
+    add_handler!(PutStudyPruner);
+    add_handler!(PutStudyTrialBudget);
+    add_handler!(DeleteStudyTrialBudget);
+    add_handler!(PutStudyState);
+    add_handler!(PutStudyLifetime);
+    add_handler!(DeleteStudyLifetime);
+    add_handler!(PostStudyKeepAlive);
+    add_handler!(PutStudySearchSpace);
+    add_handler!(GetStudySearchSpace);
+    add_handler!(PostStudySubscribe);
+    add_handler!(GetNewEvents);
+    add_handler!(PostTrial);
+    add_handler!(PutTrialState);
+    add_handler!(PutTrialParam);
+    add_handler!(PutTrialValue);
+    add_handler!(PutTrialConstraints);
+    add_handler!(PutTrialIntermediateValue);
+    add_handler!(PutTrialSystemAttr);
+    add_handler!(PutTrialUserAttr);
+    add_handler!(PostTrialSample);
+    add_handler!(GetTrialShouldPrune);
+    add_handler!(PostTrialShouldPrune);
+    add_handler!(GetTrial);
+    add_handler!(GetTrials);
+    add_handler!(GetBestTrials);
+    add_handler!(GetStudyAudit);
+    add_handler!(GetParamImportances);
+    add_handler!(GetStudyStats);
+    add_handler!(GetLearningCurves);
 
     let server = builder.finish(fibers_global::handle());
+    let (server, server_addr) = track!(fibers_global::execute(server.local_addr()))?;
     fibers_global::spawn(server.map_err(|e| panic!("{}", e)));
+    if let Some(options) = tls_options {
+        let config = track!(options.server_config())?;
+        track!(TlsTerminator::bind(http_addr, config, server_addr))?.spawn();
+    }
 
     if opt.exit_if_stdin_close {
         std::thread::spawn(|| {
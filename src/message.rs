@@ -1,14 +1,113 @@
+use crate::cluster_key::{ClusterKey, Seal};
 use crate::{global, study};
-use crate::{ErrorKind, Result};
+use crate::{Error, ErrorKind, Result};
 use bytecodec::json_codec::{JsonDecoder, JsonEncoder};
 use plumcast::message::MessagePayload;
+use std::convert::TryInto;
+use trackable::error::ErrorKindExt;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UnionMessage {
     Global(global::Message),
     Study(study::Message),
+
+    /// A message authenticated by the cluster key.
+    ///
+    /// `payload` is the JSON of the original message,
+    /// and `timestamp`, `nonce` and `tag` are the fields of its seal (`tag` is a hex string).
+    Signed {
+        payload: String,
+        timestamp: u64,
+        nonce: u64,
+        tag: String,
+    },
 }
 impl UnionMessage {
+    /// Wraps this message in a `Signed` message if `key` is given.
+    pub fn sign(self, key: Option<&ClusterKey>) -> Self {
+        if let Some(key) = key {
+            let payload = serde_json::to_string(&self).expect("never fails");
+            let seal = key.seal(payload.as_bytes());
+            let tag = seal.tag.iter().map(|b| format!("{:02x}", b)).collect();
+            UnionMessage::Signed {
+                payload,
+                timestamp: seal.timestamp,
+                nonce: seal.nonce,
+                tag,
+            }
+        } else {
+            self
+        }
+    }
+
+    /// Verifies this received message with `key` and returns the original message.
+    ///
+    /// If `key` is given, only `Signed` messages with valid, fresh and not replayed seals are accepted.
+    /// Otherwise, `Signed` messages are rejected.
+    pub fn verify(self, key: Option<&ClusterKey>) -> Result<Self> {
+        track!(self.open(key, |key, payload, seal| key.verify(payload, seal)))
+    }
+
+    /// Re-verifies a message that has already been accepted by [`UnionMessage::verify`] and returns the original message.
+    ///
+    /// Unlike `verify`, the freshness of the seal is not checked.
+    pub fn reopen(self, key: Option<&ClusterKey>) -> Result<Self> {
+        track!(self.open(key, |key, payload, seal| {
+            let seal = track_assert_some!(seal, ErrorKind::PermissionDenied);
+            track!(key.verify_tag(payload, seal))
+        }))
+    }
+
+    fn open<F>(self, key: Option<&ClusterKey>, verify: F) -> Result<Self>
+    where
+        F: FnOnce(&ClusterKey, &[u8], Option<&Seal>) -> Result<()>,
+    {
+        let key = if let Some(key) = key {
+            key
+        } else {
+            track_assert!(
+                !matches!(self, UnionMessage::Signed { .. }),
+                ErrorKind::InvalidInput,
+                "Received a signed message but no cluster key is given"
+            );
+            return Ok(self);
+        };
+
+        // Unsigned messages have no seal and are always rejected.
+        let (payload, seal) = match self {
+            UnionMessage::Signed {
+                payload,
+                timestamp,
+                nonce,
+                tag,
+            } => {
+                let tag = (0..tag.len())
+                    .step_by(2)
+                    .map(|i| {
+                        tag.get(i..i + 2)
+                            .and_then(|b| u8::from_str_radix(b, 16).ok())
+                    })
+                    .collect::<Option<Vec<_>>>();
+                let seal = tag.and_then(|tag| tag.try_into().ok()).map(|tag| Seal {
+                    timestamp,
+                    nonce,
+                    tag,
+                });
+                (payload, seal)
+            }
+            _ => (String::new(), None),
+        };
+        track!(verify(key, payload.as_bytes(), seal.as_ref()))?;
+        let message: Self = track!(serde_json::from_str(&payload)
+            .map_err(|e| Error::from(ErrorKind::InvalidInput.cause(e))))?;
+        track_assert!(
+            !matches!(message, UnionMessage::Signed { .. }),
+            ErrorKind::InvalidInput,
+            "Nested signed message"
+        );
+        Ok(message)
+    }
+
     pub fn into_global_message(self) -> Result<global::Message> {
         if let UnionMessage::Global(m) = self {
             Ok(m)
@@ -39,3 +138,51 @@ impl MessagePayload for UnionMessage {
     type Encoder = JsonEncoder<Self>;
     type Decoder = JsonDecoder<Self>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::study::{Namespace, StudyName};
+
+    fn message() -> UnionMessage {
+        UnionMessage::from(global::Message::JoinStudy {
+            namespace: Namespace::default(),
+            name: StudyName::new("foo".to_owned()),
+        })
+    }
+
+    #[test]
+    fn signed_message_works() {
+        let key = ClusterKey::new(b"foo").unwrap();
+        let signed = message().sign(Some(&key));
+        assert!(matches!(signed, UnionMessage::Signed { .. }));
+
+        // The signed message is verified after being relayed.
+        let json = serde_json::to_string(&signed).unwrap();
+        let relayed: UnionMessage = serde_json::from_str(&json).unwrap();
+        assert!(relayed
+            .verify(Some(&key))
+            .unwrap()
+            .into_global_message()
+            .is_ok());
+
+        // Replayed messages are rejected, but accepted messages can be re-verified.
+        assert!(signed.clone().verify(Some(&key)).is_err());
+        assert!(signed.clone().reopen(Some(&key)).is_ok());
+
+        let other = ClusterKey::new(b"bar").unwrap();
+        assert!(signed.clone().verify(Some(&other)).is_err());
+        assert!(signed.clone().reopen(Some(&other)).is_err());
+        assert!(signed.verify(None).is_err());
+        assert!(message().verify(Some(&key)).is_err());
+        assert_eq!(key.rejected_messages(), 2);
+        assert_eq!(other.rejected_messages(), 1);
+    }
+
+    #[test]
+    fn unsigned_message_works() {
+        let unsigned = message().sign(None);
+        assert!(matches!(unsigned, UnionMessage::Global(_)));
+        assert!(unsigned.verify(None).is_ok());
+    }
+}
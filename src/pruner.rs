@@ -0,0 +1,789 @@
+use crate::hash::fnv1a;
+use crate::study::StudyDirection;
+use crate::trial::{Trial, TrialId};
+use crate::{ErrorKind, Result};
+use serde_json::Value as JsonValue;
+
+/// Key of the trial system attribute that records the Hyperband bracket assigned to the trial.
+pub const HYPERBAND_BRACKET_ATTR_KEY: &str = "plumtuna:hyperband_bracket";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Pruner {
+    Median {
+        #[serde(default = "default_n_startup_trials")]
+        n_startup_trials: usize,
+        #[serde(default)]
+        n_warmup_steps: u32,
+    },
+    Percentile {
+        percentile: f64,
+        #[serde(default = "default_n_startup_trials")]
+        n_startup_trials: usize,
+        #[serde(default)]
+        n_warmup_steps: u32,
+    },
+    SuccessiveHalving {
+        #[serde(default = "default_min_resource")]
+        min_resource: u32,
+        #[serde(default = "default_sh_reduction_factor")]
+        reduction_factor: u32,
+        #[serde(default)]
+        min_early_stopping_rate: u32,
+    },
+    Hyperband {
+        #[serde(default = "default_min_resource")]
+        min_resource: u32,
+        max_resource: u32,
+        #[serde(default = "default_hb_reduction_factor")]
+        reduction_factor: u32,
+        #[serde(default)]
+        n_brackets: Option<u32>,
+    },
+}
+impl Pruner {
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            Pruner::Median { .. } => {}
+            Pruner::Percentile { percentile, .. } => {
+                track_assert!(
+                    (0.0..=100.0).contains(&percentile),
+                    ErrorKind::InvalidInput,
+                    "Percentile must be in [0, 100]: {}",
+                    percentile
+                );
+            }
+            Pruner::SuccessiveHalving {
+                min_resource,
+                reduction_factor,
+                ..
+            } => {
+                track!(validate_resource(min_resource, reduction_factor))?;
+            }
+            Pruner::Hyperband {
+                min_resource,
+                max_resource,
+                reduction_factor,
+                n_brackets,
+            } => {
+                track!(validate_resource(min_resource, reduction_factor))?;
+                track_assert!(
+                    min_resource <= max_resource,
+                    ErrorKind::InvalidInput,
+                    "`max_resource` must be greater than or equal to `min_resource`"
+                );
+                track_assert!(
+                    n_brackets != Some(0),
+                    ErrorKind::InvalidInput,
+                    "`n_brackets` must be a positive number"
+                );
+                track_assert!(
+                    n_brackets.is_none_or(|n| n <= MAX_N_BRACKETS),
+                    ErrorKind::InvalidInput,
+                    "`n_brackets` must be less than or equal to {}",
+                    MAX_N_BRACKETS
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Decides whether `trial` should be pruned at its latest reported step.
+    ///
+    /// `trials` are all of the trials in the study (`trial` itself may be included).
+    pub fn should_prune(
+        &self,
+        direction: StudyDirection,
+        trial: &Trial,
+        trials: &[Trial],
+    ) -> PruneDecision {
+        let mut decision = PruneDecision::default();
+        decision.prune = match *self {
+            Pruner::Median {
+                n_startup_trials,
+                n_warmup_steps,
+            } => percentile_pruning(
+                direction,
+                trial,
+                trials,
+                50.0,
+                n_startup_trials,
+                n_warmup_steps,
+            ),
+            Pruner::Percentile {
+                percentile,
+                n_startup_trials,
+                n_warmup_steps,
+            } => percentile_pruning(
+                direction,
+                trial,
+                trials,
+                percentile,
+                n_startup_trials,
+                n_warmup_steps,
+            ),
+            Pruner::SuccessiveHalving {
+                min_resource,
+                reduction_factor,
+                min_early_stopping_rate,
+            } => {
+                let rungs = Rungs {
+                    min_resource,
+                    reduction_factor,
+                    min_early_stopping_rate,
+                };
+                let trials = trials.iter().collect::<Vec<_>>();
+                rungs.should_prune(direction, trial, &trials, &mut decision)
+            }
+            Pruner::Hyperband {
+                min_resource,
+                max_resource,
+                reduction_factor,
+                n_brackets,
+            } => {
+                let n_brackets = n_brackets.unwrap_or_else(|| {
+                    let ratio = f64::from(max_resource) / f64::from(min_resource);
+                    (ratio.ln() / f64::from(reduction_factor).ln()).floor() as u32 + 1
+                });
+                let brackets = Brackets {
+                    n_brackets,
+                    reduction_factor,
+                };
+                let bracket = brackets.bracket_of(trial);
+                if !trial.system_attrs.contains_key(HYPERBAND_BRACKET_ATTR_KEY) {
+                    decision
+                        .system_attrs
+                        .push((HYPERBAND_BRACKET_ATTR_KEY.to_owned(), bracket.into()));
+                }
+
+                let trials = trials
+                    .iter()
+                    .filter(|t| brackets.bracket_of(t) == bracket)
+                    .collect::<Vec<_>>();
+                let rungs = Rungs {
+                    min_resource,
+                    reduction_factor,
+                    min_early_stopping_rate: bracket,
+                };
+                rungs.should_prune(direction, trial, &trials, &mut decision)
+            }
+        };
+        decision
+    }
+}
+
+/// The result of [`Pruner::should_prune`].
+///
+/// `system_attrs` are the bookkeeping attributes (e.g., completed rungs) that should be set to the trial.
+#[derive(Debug, Default)]
+pub struct PruneDecision {
+    pub prune: bool,
+    pub system_attrs: Vec<(String, JsonValue)>,
+}
+
+/// Returns the `p`-th percentile of `values` using linear interpolation.
+pub fn percentile_of(values: &mut [f64], p: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).expect("never fails"));
+
+    let rank = (p / 100.0) * (values.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let fraction = rank - lower as f64;
+    Some(values[lower] + (values[upper] - values[lower]) * fraction)
+}
+
+fn percentile_pruning(
+    direction: StudyDirection,
+    trial: &Trial,
+    trials: &[Trial],
+    percentile: f64,
+    n_startup_trials: usize,
+    n_warmup_steps: u32,
+) -> bool {
+    let (step, value) = match trial.intermediate_values.iter().next_back() {
+        None => return false,
+        Some((&step, &value)) => (step, value),
+    };
+    if step < n_warmup_steps {
+        return false;
+    }
+
+    let completed = trials
+        .iter()
+        .filter(|t| t.is_complete() && t.trial_id != trial.trial_id)
+        .collect::<Vec<_>>();
+    if completed.len() < n_startup_trials {
+        return false;
+    }
+
+    let mut values = completed
+        .iter()
+        .filter_map(|t| t.intermediate_values.get(&step).cloned())
+        .filter(|v| !v.is_nan())
+        .collect::<Vec<_>>();
+    if value.is_nan() {
+        return !values.is_empty();
+    }
+
+    if direction == StudyDirection::Maximize {
+        percentile_of(&mut values, 100.0 - percentile).is_some_and(|p| value < p)
+    } else {
+        percentile_of(&mut values, percentile).is_some_and(|p| value > p)
+    }
+}
+
+/// Asynchronous successive halving.
+///
+/// The value of a trial that reached the `k`-th rung is recorded in the `completed_rung_{k}` system attribute,
+/// as Optuna's `SuccessiveHalvingPruner` does.
+#[derive(Debug)]
+struct Rungs {
+    min_resource: u32,
+    reduction_factor: u32,
+    min_early_stopping_rate: u32,
+}
+impl Rungs {
+    fn should_prune(
+        &self,
+        direction: StudyDirection,
+        trial: &Trial,
+        trials: &[&Trial],
+        decision: &mut PruneDecision,
+    ) -> bool {
+        let (step, value) = match trial.intermediate_values.iter().next_back() {
+            None => return false,
+            Some((&step, &value)) => (step, value),
+        };
+
+        let mut rung = (0..)
+            .take_while(|&r| trial.system_attrs.contains_key(&rung_key(r)))
+            .count() as u32;
+        loop {
+            let promotion_step = u64::from(self.reduction_factor)
+                .checked_pow(self.min_early_stopping_rate + rung)
+                .and_then(|x| x.checked_mul(u64::from(self.min_resource)))
+                .unwrap_or(u64::MAX);
+            if u64::from(step) < promotion_step {
+                return false;
+            }
+            if value.is_nan() {
+                return true;
+            }
+
+            let key = rung_key(rung);
+            decision.system_attrs.push((key.clone(), value.into()));
+
+            let mut competing_values = trials
+                .iter()
+                .filter(|t| t.trial_id != trial.trial_id)
+                .filter_map(|t| t.system_attrs.get(&key).and_then(|v| v.as_f64()))
+                .collect::<Vec<_>>();
+            competing_values.push(value);
+            if !self.is_promotable(direction, value, &mut competing_values) {
+                return true;
+            }
+            rung += 1;
+        }
+    }
+
+    fn is_promotable(
+        &self,
+        direction: StudyDirection,
+        value: f64,
+        competing_values: &mut [f64],
+    ) -> bool {
+        let promotable_index = (competing_values.len() / self.reduction_factor as usize).max(1) - 1;
+        competing_values.sort_by(|a, b| a.partial_cmp(b).expect("never fails"));
+        if direction == StudyDirection::Maximize {
+            value >= competing_values[competing_values.len() - 1 - promotable_index]
+        } else {
+            value <= competing_values[promotable_index]
+        }
+    }
+}
+
+/// The maximum number of Hyperband brackets (the default number never exceeds this since resources are `u32`).
+const MAX_N_BRACKETS: u32 = 32;
+
+/// Hyperband bracket assignment.
+///
+/// A trial is assigned to a bracket by the hash of its ID, weighted by the budget of each bracket.
+/// The hash function (FNV-1a) is fixed so that all nodes assign a trial to the same bracket.
+#[derive(Debug)]
+struct Brackets {
+    n_brackets: u32,
+    reduction_factor: u32,
+}
+impl Brackets {
+    fn bracket_of(&self, trial: &Trial) -> u32 {
+        trial
+            .system_attrs
+            .get(HYPERBAND_BRACKET_ATTR_KEY)
+            .and_then(|v| v.as_u64())
+            .map(|b| b as u32)
+            .unwrap_or_else(|| self.assign(&trial.trial_id))
+    }
+
+    fn assign(&self, trial_id: &TrialId) -> u32 {
+        let budgets = (0..self.n_brackets)
+            .map(|i| {
+                let s = self.n_brackets - 1 - i;
+                let budget = f64::from(self.n_brackets)
+                    * f64::from(self.reduction_factor).powi(s as i32)
+                    / f64::from(s + 1);
+                budget.ceil() as u64
+            })
+            .collect::<Vec<_>>();
+
+        let total = budgets.iter().fold(0u64, |a, b| a.saturating_add(*b));
+        let mut n = fnv1a(trial_id.as_str().as_bytes()) % total;
+        for (i, budget) in budgets.into_iter().enumerate() {
+            if n < budget {
+                return i as u32;
+            }
+            n -= budget;
+        }
+        unreachable!()
+    }
+}
+
+fn rung_key(rung: u32) -> String {
+    format!("completed_rung_{}", rung)
+}
+
+/// Returns `true` if `key` is a system attribute key written by the successive halving and Hyperband pruners.
+pub fn is_bookkeeping_attr_key(key: &str) -> bool {
+    key == HYPERBAND_BRACKET_ATTR_KEY
+        || key
+            .strip_prefix("completed_rung_")
+            .is_some_and(|rung| rung.parse::<u32>().is_ok())
+}
+
+fn validate_resource(min_resource: u32, reduction_factor: u32) -> Result<()> {
+    track_assert!(
+        min_resource >= 1,
+        ErrorKind::InvalidInput,
+        "`min_resource` must be a positive number"
+    );
+    track_assert!(
+        reduction_factor >= 2,
+        ErrorKind::InvalidInput,
+        "`reduction_factor` must be greater than or equal to 2"
+    );
+    Ok(())
+}
+
+fn default_n_startup_trials() -> usize {
+    5
+}
+
+fn default_min_resource() -> u32 {
+    1
+}
+
+fn default_sh_reduction_factor() -> u32 {
+    4
+}
+
+fn default_hb_reduction_factor() -> u32 {
+    3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::study::StudyId;
+    use crate::time::Timestamp;
+    use crate::trial::TrialState;
+
+    fn trial(study_id: &StudyId, values: &[(u32, f64)], complete: bool) -> Trial {
+        let mut trial = Trial::new(TrialId::new(study_id));
+        trial.intermediate_values.extend(values.iter().cloned());
+        if complete {
+            trial.value = values.last().map(|v| v.1);
+            trial.set_state(TrialState::Complete, Timestamp::now());
+        }
+        trial
+    }
+
+    fn completed_trials(study_id: &StudyId) -> Vec<Trial> {
+        (1..=5)
+            .map(|i| trial(study_id, &[(0, 0.0), (1, f64::from(i))], true))
+            .collect()
+    }
+
+    fn median(n_startup_trials: usize, n_warmup_steps: u32) -> Pruner {
+        Pruner::Median {
+            n_startup_trials,
+            n_warmup_steps,
+        }
+    }
+
+    #[test]
+    fn percentile_of_works() {
+        assert_eq!(percentile_of(&mut [], 50.0), None);
+        assert_eq!(percentile_of(&mut [3.0, 1.0, 2.0], 50.0), Some(2.0));
+        assert_eq!(percentile_of(&mut [4.0, 1.0, 3.0, 2.0], 50.0), Some(2.5));
+        assert_eq!(percentile_of(&mut [4.0, 1.0, 3.0, 2.0], 0.0), Some(1.0));
+        assert_eq!(percentile_of(&mut [4.0, 1.0, 3.0, 2.0], 100.0), Some(4.0));
+    }
+
+    #[test]
+    fn median_pruner_works() {
+        let study_id = StudyId::new();
+        let trials = completed_trials(&study_id);
+        let pruner = median(5, 0);
+
+        let worse = trial(&study_id, &[(1, 4.0)], false);
+        let better = trial(&study_id, &[(1, 2.0)], false);
+        assert!(
+            pruner
+                .should_prune(StudyDirection::Minimize, &worse, &trials)
+                .prune
+        );
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Minimize, &better, &trials)
+                .prune
+        );
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Maximize, &worse, &trials)
+                .prune
+        );
+        assert!(
+            pruner
+                .should_prune(StudyDirection::Maximize, &better, &trials)
+                .prune
+        );
+
+        // Trials without intermediate values are never pruned.
+        let empty = trial(&study_id, &[], false);
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Minimize, &empty, &trials)
+                .prune
+        );
+
+        // NaN is regarded as the worst value.
+        let nan = trial(&study_id, &[(1, f64::NAN)], false);
+        assert!(
+            pruner
+                .should_prune(StudyDirection::Minimize, &nan, &trials)
+                .prune
+        );
+        assert!(
+            pruner
+                .should_prune(StudyDirection::Maximize, &nan, &trials)
+                .prune
+        );
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Minimize, &nan, &[])
+                .prune
+        );
+    }
+
+    #[test]
+    fn median_pruner_respects_startup_and_warmup() {
+        let study_id = StudyId::new();
+        let trials = completed_trials(&study_id);
+        let worse = trial(&study_id, &[(1, 4.0)], false);
+
+        let pruner = median(6, 0);
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Minimize, &worse, &trials)
+                .prune
+        );
+
+        let pruner = median(5, 2);
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Minimize, &worse, &trials)
+                .prune
+        );
+
+        // The trial itself is not counted as a startup trial even if it has completed.
+        let mut trials = trials;
+        let worse = trial(&study_id, &[(1, 4.0)], true);
+        trials.pop();
+        trials.push(worse.clone());
+        let pruner = median(5, 0);
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Minimize, &worse, &trials)
+                .prune
+        );
+    }
+
+    #[test]
+    fn percentile_pruner_works() {
+        let study_id = StudyId::new();
+        let trials = completed_trials(&study_id);
+        let pruner = Pruner::Percentile {
+            percentile: 25.0,
+            n_startup_trials: 5,
+            n_warmup_steps: 0,
+        };
+
+        // The 25th percentile of [1, 2, 3, 4, 5] is 2 and the 75th is 4.
+        let t = trial(&study_id, &[(1, 2.5)], false);
+        assert!(
+            pruner
+                .should_prune(StudyDirection::Minimize, &t, &trials)
+                .prune
+        );
+        assert!(
+            pruner
+                .should_prune(StudyDirection::Maximize, &t, &trials)
+                .prune
+        );
+
+        let t = trial(&study_id, &[(1, 1.5)], false);
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Minimize, &t, &trials)
+                .prune
+        );
+
+        let t = trial(&study_id, &[(1, 4.5)], false);
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Maximize, &t, &trials)
+                .prune
+        );
+
+        // Steps that no completed trial has reported are not compared.
+        let t = trial(&study_id, &[(2, 100.0)], false);
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Minimize, &t, &trials)
+                .prune
+        );
+    }
+
+    #[test]
+    fn percentile_pruner_validation_works() {
+        let pruner = |percentile| Pruner::Percentile {
+            percentile,
+            n_startup_trials: 5,
+            n_warmup_steps: 0,
+        };
+        assert!(pruner(0.0).validate().is_ok());
+        assert!(pruner(100.0).validate().is_ok());
+        assert!(pruner(-1.0).validate().is_err());
+        assert!(pruner(100.1).validate().is_err());
+    }
+
+    fn with_attrs(mut trial: Trial, attrs: &[(String, JsonValue)]) -> Trial {
+        trial.system_attrs.extend(attrs.iter().cloned());
+        trial
+    }
+
+    fn successive_halving() -> Pruner {
+        Pruner::SuccessiveHalving {
+            min_resource: 1,
+            reduction_factor: 2,
+            min_early_stopping_rate: 0,
+        }
+    }
+
+    #[test]
+    fn successive_halving_pruner_records_rungs() {
+        let study_id = StudyId::new();
+        let pruner = successive_halving();
+
+        // The first rung is reached at step `min_resource`.
+        let t = trial(&study_id, &[(0, 1.0)], false);
+        let decision = pruner.should_prune(StudyDirection::Minimize, &t, &[]);
+        assert!(!decision.prune);
+        assert!(decision.system_attrs.is_empty());
+
+        // Steps 1 and 2 reach the rungs 0 and 1, and the trial alone is always promoted.
+        let t = trial(&study_id, &[(2, 1.0)], false);
+        let decision = pruner.should_prune(StudyDirection::Minimize, &t, &[]);
+        assert!(!decision.prune);
+        assert_eq!(
+            decision.system_attrs,
+            vec![(rung_key(0), 1.0.into()), (rung_key(1), 1.0.into())]
+        );
+
+        // Rungs that have already been recorded are skipped.
+        let t = with_attrs(t, &decision.system_attrs);
+        let decision = pruner.should_prune(StudyDirection::Minimize, &t, &[]);
+        assert!(!decision.prune);
+        assert!(decision.system_attrs.is_empty());
+    }
+
+    #[test]
+    fn successive_halving_pruner_promotes_top_trials() {
+        let study_id = StudyId::new();
+        let pruner = successive_halving();
+        let others = (1..=3)
+            .map(|i| {
+                let attrs = [(rung_key(0), f64::from(i).into())];
+                with_attrs(trial(&study_id, &[(1, f64::from(i))], false), &attrs)
+            })
+            .collect::<Vec<_>>();
+
+        // The top half of [1, 2, 3, value] in the rung 0 is promoted.
+        let t = trial(&study_id, &[(1, 1.5)], false);
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Minimize, &t, &others)
+                .prune
+        );
+        assert!(
+            pruner
+                .should_prune(StudyDirection::Maximize, &t, &others)
+                .prune
+        );
+
+        let t = trial(&study_id, &[(1, 2.5)], false);
+        assert!(
+            pruner
+                .should_prune(StudyDirection::Minimize, &t, &others)
+                .prune
+        );
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Maximize, &t, &others)
+                .prune
+        );
+
+        let t = trial(&study_id, &[(1, f64::NAN)], false);
+        assert!(
+            pruner
+                .should_prune(StudyDirection::Minimize, &t, &others)
+                .prune
+        );
+    }
+
+    #[test]
+    fn successive_halving_pruner_respects_min_early_stopping_rate() {
+        let study_id = StudyId::new();
+        let pruner = Pruner::SuccessiveHalving {
+            min_resource: 2,
+            reduction_factor: 3,
+            min_early_stopping_rate: 1,
+        };
+
+        // The first rung is reached at step `min_resource * reduction_factor^min_early_stopping_rate`.
+        let t = trial(&study_id, &[(5, 1.0)], false);
+        let decision = pruner.should_prune(StudyDirection::Minimize, &t, &[]);
+        assert!(decision.system_attrs.is_empty());
+
+        let t = trial(&study_id, &[(6, 1.0)], false);
+        let decision = pruner.should_prune(StudyDirection::Minimize, &t, &[]);
+        assert_eq!(decision.system_attrs, vec![(rung_key(0), 1.0.into())]);
+    }
+
+    #[test]
+    fn hyperband_pruner_assigns_brackets() {
+        let study_id = StudyId::new();
+        let pruner = Pruner::Hyperband {
+            min_resource: 1,
+            max_resource: 9,
+            reduction_factor: 3,
+            n_brackets: None,
+        };
+
+        // `log_3(9 / 1) + 1 = 3` brackets are used by default.
+        let mut counts = [0; 3];
+        for _ in 0..300 {
+            let t = trial(&study_id, &[(0, 1.0)], false);
+            let decision = pruner.should_prune(StudyDirection::Minimize, &t, &[]);
+            assert_eq!(decision.system_attrs.len(), 1);
+            let (key, bracket) = &decision.system_attrs[0];
+            assert_eq!(key, HYPERBAND_BRACKET_ATTR_KEY);
+            let bracket = bracket.as_u64().unwrap() as usize;
+            counts[bracket] += 1;
+
+            // The assignment only depends on the trial ID and is recorded only once.
+            let again = pruner.should_prune(StudyDirection::Minimize, &t, &[]);
+            assert_eq!(again.system_attrs, decision.system_attrs);
+            let t = with_attrs(t, &decision.system_attrs);
+            let again = pruner.should_prune(StudyDirection::Minimize, &t, &[]);
+            assert!(again.system_attrs.is_empty());
+        }
+
+        // The brackets with smaller early stopping rates (i.e., larger budgets) get more trials.
+        assert!(counts.iter().all(|&n| n > 0), "{:?}", counts);
+        assert!(counts[0] > counts[2], "{:?}", counts);
+    }
+
+    #[test]
+    fn hyperband_pruner_only_compares_trials_in_the_same_bracket() {
+        let study_id = StudyId::new();
+        let pruner = Pruner::Hyperband {
+            min_resource: 1,
+            max_resource: 9,
+            reduction_factor: 3,
+            n_brackets: None,
+        };
+        let others = |bracket: u32| {
+            (1..=3)
+                .map(|i| {
+                    let attrs = [
+                        (HYPERBAND_BRACKET_ATTR_KEY.to_owned(), bracket.into()),
+                        (rung_key(0), f64::from(i).into()),
+                    ];
+                    with_attrs(trial(&study_id, &[(1, f64::from(i))], false), &attrs)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let t = trial(&study_id, &[(1, 4.0)], false);
+        let t = with_attrs(t, &[(HYPERBAND_BRACKET_ATTR_KEY.to_owned(), 0.into())]);
+        assert!(
+            pruner
+                .should_prune(StudyDirection::Minimize, &t, &others(0))
+                .prune
+        );
+        assert!(
+            !pruner
+                .should_prune(StudyDirection::Minimize, &t, &others(1))
+                .prune
+        );
+    }
+
+    #[test]
+    fn resource_validation_works() {
+        let hyperband =
+            |min_resource, max_resource, reduction_factor, n_brackets| Pruner::Hyperband {
+                min_resource,
+                max_resource,
+                reduction_factor,
+                n_brackets,
+            };
+        assert!(hyperband(1, 9, 3, None).validate().is_ok());
+        assert!(hyperband(1, 9, 3, Some(MAX_N_BRACKETS)).validate().is_ok());
+        assert!(hyperband(0, 9, 3, None).validate().is_err());
+        assert!(hyperband(1, 9, 1, None).validate().is_err());
+        assert!(hyperband(10, 9, 3, None).validate().is_err());
+        assert!(hyperband(1, 9, 3, Some(0)).validate().is_err());
+        assert!(hyperband(1, 9, 3, Some(MAX_N_BRACKETS + 1))
+            .validate()
+            .is_err());
+
+        let successive_halving = Pruner::SuccessiveHalving {
+            min_resource: 1,
+            reduction_factor: 1,
+            min_early_stopping_rate: 0,
+        };
+        assert!(successive_halving.validate().is_err());
+    }
+
+    #[test]
+    fn bookkeeping_attr_keys() {
+        assert!(is_bookkeeping_attr_key(HYPERBAND_BRACKET_ATTR_KEY));
+        assert!(is_bookkeeping_attr_key("completed_rung_0"));
+        assert!(is_bookkeeping_attr_key("completed_rung_12"));
+        assert!(!is_bookkeeping_attr_key("completed_rung_"));
+        assert!(!is_bookkeeping_attr_key("completed_rung_x"));
+        assert!(!is_bookkeeping_attr_key("foo"));
+    }
+}
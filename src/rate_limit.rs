@@ -0,0 +1,174 @@
+use crate::study::StudyId;
+use crate::{Error, ErrorKind, Result};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use trackable::error::ErrorKindExt;
+
+/// The number of buckets above which idle (i.e., full) buckets are discarded.
+const MAX_IDLE_BUCKETS: usize = 1024;
+
+/// Configuration of a token bucket.
+///
+/// The textual representation is `<RATE>[:<BURST>]` (e.g., `100:200`).
+/// If `<BURST>` is omitted, it is the same as `<RATE>`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// The number of tokens refilled per second.
+    pub rate: f64,
+
+    /// The capacity of the bucket.
+    pub burst: f64,
+}
+impl FromStr for RateLimit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut tokens = s.splitn(2, ':');
+        let rate: f64 = track!(tokens
+            .next()
+            .expect("never fails")
+            .parse()
+            .map_err(|e| Error::from(ErrorKind::InvalidInput.cause(e))); s)?;
+        let burst: f64 = if let Some(burst) = tokens.next() {
+            track!(burst
+                .parse()
+                .map_err(|e| Error::from(ErrorKind::InvalidInput.cause(e))); s)?
+        } else {
+            rate
+        };
+        track_assert!(
+            rate > 0.0 && burst >= 1.0,
+            ErrorKind::InvalidInput,
+            "The rate must be positive and the burst must be at least 1: {:?}",
+            s
+        );
+        Ok(RateLimit { rate, burst })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last_refill: Instant,
+}
+impl TokenBucket {
+    pub fn new(limit: RateLimit, now: Instant) -> Self {
+        TokenBucket {
+            limit,
+            tokens: limit.burst,
+            last_refill: now,
+        }
+    }
+
+    /// Takes a token if available.
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the time until a token becomes available.
+    pub fn wait_time(&self) -> Duration {
+        Duration::from_secs_f64((1.0 - self.tokens).max(0.0) / self.limit.rate)
+    }
+
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.limit.burst
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.limit.rate).min(self.limit.burst);
+        self.last_refill = now;
+    }
+}
+
+/// Token buckets keyed by studies or clients.
+#[derive(Debug)]
+struct Buckets<K> {
+    limit: RateLimit,
+    buckets: Mutex<HashMap<K, TokenBucket>>,
+}
+impl<K: Eq + Hash + Clone> Buckets<K> {
+    fn new(limit: RateLimit) -> Self {
+        Buckets {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn try_acquire(&self, key: &K, now: Instant) -> bool {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            buckets.retain(|_, b| !b.is_full(now));
+        }
+        let limit = self.limit;
+        buckets
+            .entry(key.clone())
+            .or_insert_with(|| TokenBucket::new(limit, now))
+            .try_acquire(now)
+    }
+}
+
+/// Rate limiter for write requests to the HTTP API.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    per_study: Option<Buckets<StudyId>>,
+    per_client: Option<Buckets<(String, bool)>>,
+}
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn per_study(&mut self, limit: RateLimit) -> &mut Self {
+        self.per_study = Some(Buckets::new(limit));
+        self
+    }
+
+    /// Note that requests from unidentified clients are only limited by the per-study limit.
+    ///
+    /// Verified and unverified identities (see `check`) have separate buckets even if their names are the same.
+    pub fn per_client(&mut self, limit: RateLimit) -> &mut Self {
+        self.per_client = Some(Buckets::new(limit));
+        self
+    }
+
+    /// Consumes a token of `study_id` and `client` (if any).
+    ///
+    /// `verified` indicates whether `client` is the principal of an authenticated token.
+    pub fn check(
+        &self,
+        study_id: Option<&StudyId>,
+        client: Option<&str>,
+        verified: bool,
+    ) -> Result<()> {
+        let now = Instant::now();
+        if let (Some(buckets), Some(study_id)) = (&self.per_study, study_id) {
+            track_assert!(
+                buckets.try_acquire(study_id, now),
+                ErrorKind::RateLimited,
+                "Too many requests to the study {:?}",
+                study_id
+            );
+        }
+        if let (Some(buckets), Some(client)) = (&self.per_client, client) {
+            track_assert!(
+                buckets.try_acquire(&(client.to_owned(), verified), now),
+                ErrorKind::RateLimited,
+                "Too many requests from the client {:?}",
+                client
+            );
+        }
+        Ok(())
+    }
+}
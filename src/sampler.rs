@@ -0,0 +1,425 @@
+use crate::distribution::Distribution;
+use crate::hash::fnv1a;
+use crate::trial::TrialParamValue;
+use crate::{ErrorKind, Result};
+use plumcast::node::NodeId;
+use std::collections::{BTreeMap, HashMap};
+
+/// Key of the trial system attribute that records the position in the QMC sequence used by the trial.
+pub const QMC_INDEX_ATTR_KEY: &str = "plumtuna:qmc_index";
+
+/// Key of the trial system attribute that records the seed of the QMC sequence used by the trial.
+pub const QMC_SEED_ATTR_KEY: &str = "plumtuna:qmc_seed";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Sampler {
+    Qmc { qmc_type: QmcType },
+}
+impl Sampler {
+    pub fn sample(
+        &self,
+        index: QmcIndex,
+        search_space: &BTreeMap<String, Distribution>,
+    ) -> Result<HashMap<String, TrialParamValue>> {
+        match self {
+            Sampler::Qmc { qmc_type } => {
+                let mut params = HashMap::new();
+                for (dim, (name, distribution)) in search_space.iter().enumerate() {
+                    let shift = digital_shift(index.seed, dim);
+                    let u = track!(qmc_type.shifted_point(index.position, dim, shift))?;
+                    let value = TrialParamValue::new(
+                        from_unit_interval(distribution, u),
+                        distribution.clone(),
+                    );
+                    params.insert(name.clone(), value);
+                }
+                Ok(params)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum QmcType {
+    Halton,
+    Sobol,
+}
+impl QmcType {
+    /// Returns the `dim`-th coordinate of the `index`-th point of the sequence.
+    pub fn point(self, index: u64, dim: usize) -> Result<f64> {
+        track!(self.shifted_point(index, dim, 0))
+    }
+
+    /// Returns the `dim`-th coordinate of the `index`-th point of the sequence digitally shifted by `shift`.
+    ///
+    /// The digits of `shift` are added (modulo the base) to those of the coordinate,
+    /// which preserves the low-discrepancy property of the sequence.
+    pub fn shifted_point(self, index: u64, dim: usize, shift: u64) -> Result<f64> {
+        match self {
+            QmcType::Halton => Ok(radical_inverse(index, nth_prime(dim), shift)),
+            QmcType::Sobol => {
+                track_assert!(
+                    dim < SOBOL_DIRECTIONS.len() + 1,
+                    ErrorKind::InvalidInput,
+                    "Sobol sequence supports up to {} dimensions",
+                    SOBOL_DIRECTIONS.len() + 1
+                );
+                Ok(sobol(index, dim, shift))
+            }
+        }
+    }
+}
+
+/// A point of the randomized QMC sequence of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QmcIndex {
+    /// The seed of the digital shift applied to the sequence (`0` means no shift).
+    pub seed: u64,
+
+    /// The position in the sequence.
+    pub position: u64,
+}
+
+/// Allocates QMC sequence indices on a node.
+///
+/// The seed of the indices is the FNV-1a hash of the node ID and the positions are given by a local counter.
+/// The sampler digitally shifts the points by values derived from the seed,
+/// so each node samples its own randomized low-discrepancy sequence and nodes do not produce the same points.
+#[derive(Debug)]
+pub struct QmcIndexAllocator {
+    seed: u64,
+    next: u64,
+}
+impl QmcIndexAllocator {
+    pub fn new(node_id: NodeId) -> Self {
+        let seed = fnv1a(node_id.to_string().as_bytes());
+        QmcIndexAllocator { seed, next: 0 }
+    }
+
+    pub fn allocate(&mut self) -> QmcIndex {
+        let index = QmcIndex {
+            seed: self.seed,
+            position: self.next,
+        };
+        self.next += 1;
+        index
+    }
+}
+
+fn from_unit_interval(distribution: &Distribution, u: f64) -> f64 {
+    match distribution.to_modern() {
+        Distribution::Float {
+            low,
+            high,
+            log: true,
+            ..
+        } => (low.ln() + u * (high.ln() - low.ln()))
+            .exp()
+            .max(low)
+            .min(high),
+        Distribution::Float {
+            low,
+            high,
+            step: Some(q),
+            ..
+        } => quantize(low, high, q, u, false),
+        Distribution::Float { low, high, .. } => low + u * (high - low),
+        Distribution::Int {
+            low,
+            high,
+            log,
+            step,
+        } => quantize(low as f64, high as f64, step as f64, u, log),
+        Distribution::Categorical { ref choices } => {
+            let n = choices.len();
+            ((u * n as f64) as usize).min(n.saturating_sub(1)) as f64
+        }
+        _ => unreachable!(),
+    }
+}
+
+// Maps `u` onto `[low - q/2, high + q/2]` (in log scale if `log` is `true`) and rounds it to the nearest multiple of `q` from `low`.
+fn quantize(low: f64, high: f64, q: f64, u: f64, log: bool) -> f64 {
+    let high = low + ((high - low) / q).floor() * q;
+    let (lower, upper) = (low - q / 2.0, high + q / 2.0);
+    let v = if log {
+        (lower.ln() + u * (upper.ln() - lower.ln())).exp()
+    } else {
+        lower + u * (upper - lower)
+    };
+    let v = ((v - low) / q).round() * q + low;
+    v.max(low).min(high)
+}
+
+/// Returns a pseudo-random shift for the `dim`-th coordinate of the sequence seeded by `seed` (`0` means no shift).
+fn digital_shift(seed: u64, dim: usize) -> u64 {
+    if seed == 0 {
+        return 0;
+    }
+
+    // SplitMix64
+    let mut z = seed
+        .wrapping_add((dim as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn radical_inverse(mut index: u64, base: u64, mut shift: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut factor = inv_base;
+    let mut x = 0.0;
+    while (index > 0 || shift > 0) && factor > f64::EPSILON / 2.0 {
+        x += ((index % base + shift % base) % base) as f64 * factor;
+        index /= base;
+        shift /= base;
+        factor *= inv_base;
+    }
+
+    // The sum of the digits may be rounded to `1.0`.
+    x.min(1.0 - f64::EPSILON / 2.0)
+}
+
+fn nth_prime(n: usize) -> u64 {
+    let mut count = 0;
+    let mut candidate = 1;
+    while count <= n {
+        candidate += 1;
+        if (2..)
+            .take_while(|d| d * d <= candidate)
+            .all(|d| candidate % d != 0)
+        {
+            count += 1;
+        }
+    }
+    candidate
+}
+
+fn sobol(index: u64, dim: usize, shift: u64) -> f64 {
+    let directions = sobol_directions(dim);
+    let mut x = shift;
+    for (bit, v) in directions.iter().enumerate() {
+        if index & (1 << bit) != 0 {
+            x ^= v;
+        }
+    }
+    // Only the upper 53 bits are used since `f64` cannot represent the others (and the result might be rounded to `1.0`).
+    (x >> 11) as f64 / 2f64.powi(53)
+}
+
+fn sobol_directions(dim: usize) -> [u64; 64] {
+    let mut v = [0; 64];
+    if dim == 0 {
+        for (i, v) in v.iter_mut().enumerate() {
+            *v = 1 << (63 - i);
+        }
+        return v;
+    }
+
+    let (s, a, m) = SOBOL_DIRECTIONS[dim - 1];
+    for i in 0..s {
+        v[i] = m[i] << (63 - i);
+    }
+    for i in s..64 {
+        v[i] = v[i - s] ^ (v[i - s] >> s);
+        for k in 1..s {
+            v[i] ^= ((a >> (s - 1 - k)) & 1) * v[i - k];
+        }
+    }
+    v
+}
+
+// The first entries of Joe and Kuo's direction numbers (`new-joe-kuo-6.21201`): `(s, a, m_i)`.
+const SOBOL_DIRECTIONS: [(usize, u64, &[u64]); 20] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distribution::Category;
+    use plumcast::node::LocalNodeId;
+
+    fn assert_points(qmc_type: QmcType, dim: usize, expected: &[f64]) {
+        let points = (0..expected.len() as u64)
+            .map(|i| qmc_type.point(i, dim).unwrap())
+            .collect::<Vec<_>>();
+        for (a, b) in points.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-12, "{:?} != {:?}", points, expected);
+        }
+    }
+
+    #[test]
+    fn halton_sequence_works() {
+        assert_points(QmcType::Halton, 0, &[0.0, 0.5, 0.25, 0.75, 0.125]);
+        assert_points(
+            QmcType::Halton,
+            1,
+            &[0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0, 4.0 / 9.0],
+        );
+        assert_points(QmcType::Halton, 2, &[0.0, 0.2, 0.4, 0.6, 0.8]);
+    }
+
+    #[test]
+    fn sobol_sequence_works() {
+        assert_points(QmcType::Sobol, 0, &[0.0, 0.5, 0.25, 0.75, 0.125]);
+        assert_points(QmcType::Sobol, 1, &[0.0, 0.5, 0.75, 0.25, 0.625]);
+
+        assert!(QmcType::Sobol.point(0, SOBOL_DIRECTIONS.len()).is_ok());
+        assert!(QmcType::Sobol.point(0, SOBOL_DIRECTIONS.len() + 1).is_err());
+    }
+
+    #[test]
+    fn shifted_points_are_in_unit_interval() {
+        for &qmc_type in &[QmcType::Halton, QmcType::Sobol] {
+            for dim in 0..5 {
+                for &shift in &[1, 12345, u64::MAX] {
+                    let points = (0..64)
+                        .map(|i| qmc_type.shifted_point(i, dim, shift).unwrap())
+                        .collect::<Vec<_>>();
+                    assert!(points.iter().all(|&x| (0.0..1.0).contains(&x)));
+
+                    // A digital shift is a bijection, so the points are still distinct.
+                    let mut sorted = points.clone();
+                    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                    sorted.dedup();
+                    assert_eq!(sorted.len(), points.len());
+                }
+            }
+            assert_ne!(
+                qmc_type.shifted_point(1, 0, 12345).unwrap(),
+                qmc_type.point(1, 0).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn quantize_works() {
+        assert_eq!(quantize(0.0, 10.0, 2.5, 0.0, false), 0.0);
+        assert_eq!(quantize(0.0, 10.0, 2.5, 0.5, false), 5.0);
+        assert_eq!(quantize(0.0, 10.0, 2.5, 0.99, false), 10.0);
+
+        // `high` is rounded down to the grid.
+        assert!((quantize(0.0, 1.0, 0.3, 0.99, false) - 0.9).abs() < 1e-12);
+
+        // Each grid point covers the same width of the unit interval.
+        let values = (0..5)
+            .map(|i| quantize(1.0, 5.0, 1.0, (f64::from(i) + 0.5) / 5.0, false))
+            .collect::<Vec<_>>();
+        assert_eq!(values, [1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        let v = quantize(1.0, 100.0, 1.0, 0.5, true);
+        assert!(v < 50.0 && v.fract() == 0.0);
+    }
+
+    #[test]
+    fn sampled_params_are_contained_in_distributions() {
+        let mut search_space = BTreeMap::new();
+        search_space.insert(
+            "categorical".to_owned(),
+            Distribution::Categorical {
+                choices: vec![Category::Int(1), Category::Int(2), Category::Int(3)],
+            },
+        );
+        search_space.insert(
+            "float".to_owned(),
+            Distribution::Float {
+                low: -1.0,
+                high: 1.0,
+                log: false,
+                step: None,
+            },
+        );
+        search_space.insert(
+            "log_float".to_owned(),
+            Distribution::Float {
+                low: 1e-5,
+                high: 1.0,
+                log: true,
+                step: None,
+            },
+        );
+        search_space.insert(
+            "step_float".to_owned(),
+            Distribution::Float {
+                low: 0.0,
+                high: 1.0,
+                log: false,
+                step: Some(0.1),
+            },
+        );
+        search_space.insert(
+            "int".to_owned(),
+            Distribution::Int {
+                low: -3,
+                high: 7,
+                log: false,
+                step: 2,
+            },
+        );
+        search_space.insert(
+            "log_int".to_owned(),
+            Distribution::Int {
+                low: 1,
+                high: 1000,
+                log: true,
+                step: 1,
+            },
+        );
+
+        for &qmc_type in &[QmcType::Halton, QmcType::Sobol] {
+            let sampler = Sampler::Qmc { qmc_type };
+            for &seed in &[0, 1, u64::MAX] {
+                for position in 0..100 {
+                    let index = QmcIndex { seed, position };
+                    let params = sampler.sample(index, &search_space).unwrap();
+                    assert_eq!(params.len(), search_space.len());
+                    for (name, param) in params {
+                        assert!(
+                            search_space[&name].contains(param.value),
+                            "{}: {}",
+                            name,
+                            param.value
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn qmc_index_allocator_works() {
+        let node_id = |id| NodeId::new(([127, 0, 0, 1], 14000).into(), LocalNodeId::new(id));
+
+        let mut a = QmcIndexAllocator::new(node_id(1));
+        let mut b = QmcIndexAllocator::new(node_id(1));
+        let mut c = QmcIndexAllocator::new(node_id(2));
+        let (a0, a1) = (a.allocate(), a.allocate());
+        assert_eq!(a0.position, 0);
+        assert_eq!(a1.position, 1);
+        assert_eq!(a0.seed, a1.seed);
+        assert_eq!(b.allocate(), a0);
+        assert_ne!(c.allocate().seed, a0.seed);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::distribution::Distribution;
+use crate::trial::{Trial, TrialId};
+use std::collections::{BTreeMap, HashSet};
+
+pub type SearchSpace = BTreeMap<String, Distribution>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSpaceMode {
+    /// The parameters (and their distributions) that are common to all completed trials.
+    Intersection,
+
+    /// The parameters that appear in any completed trial.
+    Union,
+}
+
+/// Search spaces of the completed trials, updated every time a trial completes.
+///
+/// If a trial that has already been folded into the search spaces is modified
+/// (e.g., a late parameter arrives after the completion), the search spaces are recomputed on the next query.
+#[derive(Debug, Default)]
+pub struct SearchSpaces {
+    folded: HashSet<TrialId>,
+    intersection: Option<SearchSpace>,
+    union: SearchSpace,
+    dirty: bool,
+}
+impl SearchSpaces {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn handle_trial_update(&mut self, trial: &Trial) {
+        if self.dirty {
+            return;
+        }
+        if self.folded.contains(&trial.trial_id) {
+            self.dirty = true;
+        } else if trial.is_complete() {
+            self.fold(trial);
+        }
+    }
+
+    pub fn get<'a, I>(&mut self, mode: SearchSpaceMode, trials: I) -> SearchSpace
+    where
+        I: Iterator<Item = &'a Trial>,
+    {
+        if self.dirty {
+            *self = Self::new();
+            for trial in trials.filter(|t| t.is_complete()) {
+                self.fold(trial);
+            }
+        }
+        match mode {
+            SearchSpaceMode::Intersection => self.intersection.clone().unwrap_or_default(),
+            SearchSpaceMode::Union => self.union.clone(),
+        }
+    }
+
+    fn fold(&mut self, trial: &Trial) {
+        self.folded.insert(trial.trial_id.clone());
+        if let Some(intersection) = self.intersection.as_mut() {
+            intersection.retain(|k, d| {
+                trial
+                    .params
+                    .get(k)
+                    .is_some_and(|p| p.distribution.is_equivalent(d))
+            });
+        } else {
+            self.intersection = Some(
+                trial
+                    .params
+                    .iter()
+                    .map(|(k, p)| (k.clone(), p.distribution.clone()))
+                    .collect(),
+            );
+        }
+        for (k, p) in &trial.params {
+            self.union
+                .entry(k.clone())
+                .or_insert_with(|| p.distribution.clone());
+        }
+    }
+}
@@ -1,18 +1,59 @@
+use crate::pruner::Pruner;
+use crate::sampler::Sampler;
+use crate::search_space::SearchSpace;
 use crate::time::Seconds;
 use crate::trial::Trial;
+use crate::{Error, ErrorKind, Result};
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::time::Duration;
+use trackable::error::ErrorKindExt;
 use uuid::Uuid;
 
-pub use self::message::Message;
+pub use self::message::{Author, Message};
 pub use self::node::{StudyNode, StudyNodeHandle};
+pub use self::stats::{Quantiles, StudyStats};
 pub use self::subscriber::SubscribeId;
 
 mod message;
 mod node;
 mod operation;
+mod stats;
 mod subscriber;
 
+/// The system attribute key that records the identifier of the study from which a study was cloned.
+pub const ORIGIN_STUDY_ID_ATTR_KEY: &str = "plumtuna:origin_study_id";
+
+/// The system attribute key that records the namespace of the study from which a study was cloned.
+pub const ORIGIN_NAMESPACE_ATTR_KEY: &str = "plumtuna:origin_namespace";
+
+/// The system attribute key that records the name of the study from which a study was cloned.
+pub const ORIGIN_STUDY_NAME_ATTR_KEY: &str = "plumtuna:origin_study_name";
+
+/// The system attribute key that records the identifier of the trial from which a trial was copied.
+pub const ORIGIN_TRIAL_ID_ATTR_KEY: &str = "plumtuna:origin_trial_id";
+
+/// The scope of study names.
+///
+/// Studies that belong to different namespaces may have the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Namespace(String);
+impl Namespace {
+    pub fn new(namespace: String) -> Self {
+        Self(namespace)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+impl Default for Namespace {
+    fn default() -> Self {
+        Self("default".to_owned())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct StudyName(String);
 impl StudyName {
@@ -28,6 +69,7 @@ impl StudyName {
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct StudyId(Uuid);
 impl StudyId {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self(Uuid::new_v4())
     }
@@ -50,8 +92,94 @@ pub enum StudyDirection {
     Maximize,
 }
 
+/// The lifecycle state of a study.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum StudyState {
+    #[default]
+    Active,
+
+    /// No new trials can be created, but the existing ones can still be updated.
+    Paused,
+
+    /// Neither trials nor settings can be modified except for the state itself.
+    Finished,
+
+    /// The same as `Finished` except that it indicates that the study is kept only for reference.
+    Archived,
+}
+impl StudyState {
+    /// Returns `true` if new trials can be created in the study.
+    pub fn accepts_new_trials(self) -> bool {
+        self == StudyState::Active
+    }
+
+    /// Returns `true` if the trials and settings of the study can be modified.
+    pub fn is_writable(self) -> bool {
+        matches!(self, StudyState::Active | StudyState::Paused)
+    }
+}
+
+/// The maximum number of trials that can be created in a study.
+///
+/// Since each node checks the budget against its own view of the study,
+/// trials concurrently created on different nodes may slightly exceed the budget.
+/// The excess is bounded by the number of trials created during the propagation delay of the cluster,
+/// and is reported as `StudySummary::trial_budget_overshoot`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrialBudget {
+    pub max_trials: u32,
+
+    /// If `true`, failed trials are not counted.
+    #[serde(default)]
+    pub exclude_failed: bool,
+}
+
+/// How long a study is kept on a node after the last write or keepalive request to it.
+///
+/// The textual representation is either the number of seconds or `never`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudyLifetime {
+    /// The lifetime in seconds (`None` means that the study never expires).
+    pub seconds: Option<u64>,
+}
+impl StudyLifetime {
+    pub const NEVER: Self = StudyLifetime { seconds: None };
+
+    pub fn from_secs(seconds: u64) -> Self {
+        StudyLifetime {
+            seconds: Some(seconds),
+        }
+    }
+
+    pub fn as_duration(&self) -> Option<Duration> {
+        self.seconds.map(Duration::from_secs)
+    }
+}
+impl Default for StudyLifetime {
+    fn default() -> Self {
+        Self::from_secs(60 * 60)
+    }
+}
+impl FromStr for StudyLifetime {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "never" {
+            Ok(Self::NEVER)
+        } else {
+            let seconds = track!(s
+                .parse()
+                .map_err(|e| Error::from(ErrorKind::InvalidInput.cause(e))); s)?;
+            Ok(Self::from_secs(seconds))
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct StudyNameAndId {
+    #[serde(default)]
+    pub namespace: Namespace,
     pub study_name: StudyName,
     pub study_id: StudyId,
 }
@@ -59,11 +187,31 @@ pub struct StudyNameAndId {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StudySummary {
     pub study_id: StudyId,
+    pub namespace: Namespace,
     pub study_name: StudyName,
     pub direction: StudyDirection,
+    pub state: StudyState,
     pub user_attrs: HashMap<String, JsonValue>,
     pub system_attrs: HashMap<String, JsonValue>,
+    pub sampler: Option<Sampler>,
+    pub pruner: Option<Pruner>,
+    pub search_space: Option<SearchSpace>,
+    pub tags: BTreeMap<String, String>,
     pub n_trials: u32,
+    pub n_feasible_trials: u32,
+    pub n_infeasible_trials: u32,
     pub best_trial: Option<Trial>,
     pub datetime_start: Seconds,
+    pub trial_budget: Option<TrialBudget>,
+
+    /// The number of trials counted against `trial_budget` in excess of it.
+    pub trial_budget_overshoot: u32,
+
+    /// The lifetime set to the study (`None` means that the node default is used).
+    pub lifetime: Option<StudyLifetime>,
+
+    /// The number of seconds until the study expires on the node (`None` if it never expires).
+    ///
+    /// Only writes and keepalive requests extend the lifetime (reading the summary does not).
+    pub remaining_lifetime_sec: Option<u64>,
 }
@@ -1,64 +1,213 @@
-use crate::study::StudyDirection;
+use crate::auth::Permission;
+use crate::pruner::Pruner;
+use crate::sampler::Sampler;
+use crate::search_space::SearchSpace;
+use crate::study::{StudyDirection, StudyLifetime, StudyState, TrialBudget};
 use crate::time::Timestamp;
 use crate::trial::{TrialId, TrialParamValue, TrialState};
+use crate::webhook::Webhook;
+use plumcast::node::NodeId;
 use serde_json::Value as JsonValue;
 
+/// The node and client that issued a write message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Author {
+    pub node: NodeId,
+
+    /// The identity of the HTTP client (if known).
+    pub client: Option<String>,
+
+    /// `true` if `client` is the principal of an authenticated token.
+    ///
+    /// Otherwise, `client` is the value of the `X-Client` header, which any client can claim.
+    #[serde(default)]
+    pub verified: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Message {
     SetStudyDirection {
         direction: StudyDirection,
         timestamp: Timestamp,
+        author: Option<Author>,
     },
     SetStudyUserAttr {
         key: String,
         value: JsonValue,
         timestamp: Timestamp,
+        author: Option<Author>,
     },
     SetStudySystemAttr {
         key: String,
         value: JsonValue,
         timestamp: Timestamp,
+        author: Option<Author>,
+    },
+    SetStudySampler {
+        sampler: Sampler,
+        timestamp: Timestamp,
+        author: Option<Author>,
+    },
+    SetStudyPruner {
+        pruner: Pruner,
+        timestamp: Timestamp,
+        author: Option<Author>,
+    },
+    SetStudySearchSpace {
+        search_space: SearchSpace,
+        timestamp: Timestamp,
+        author: Option<Author>,
+    },
+    SetStudyTag {
+        key: String,
+        value: Option<String>,
+        timestamp: Timestamp,
+        author: Option<Author>,
+    },
+    SetStudyAcl {
+        principal: String,
+        permission: Option<Permission>,
+        timestamp: Timestamp,
+        author: Option<Author>,
+    },
+    SetStudyTrialBudget {
+        budget: Option<TrialBudget>,
+        timestamp: Timestamp,
+        author: Option<Author>,
+    },
+    SetStudyLifetime {
+        lifetime: Option<StudyLifetime>,
+        timestamp: Timestamp,
+        author: Option<Author>,
+    },
+    SetStudyState {
+        state: StudyState,
+        timestamp: Timestamp,
+        author: Option<Author>,
+    },
+    SetStudyWebhook {
+        name: String,
+        webhook: Option<Webhook>,
+        timestamp: Timestamp,
+        author: Option<Author>,
     },
     CreateTrial {
         trial_id: TrialId,
         timestamp: Timestamp,
+        author: Option<Author>,
     },
     SetTrialState {
         trial_id: TrialId,
         state: TrialState,
         timestamp: Timestamp,
+        author: Option<Author>,
     },
     SetTrialParam {
         trial_id: TrialId,
         key: String,
         value: TrialParamValue,
         timestamp: Timestamp,
+        author: Option<Author>,
     },
     SetTrialValue {
         trial_id: TrialId,
         value: f64,
         timestamp: Timestamp,
+        author: Option<Author>,
     },
     SetTrialIntermediateValue {
         trial_id: TrialId,
         step: u32,
         value: f64,
         timestamp: Timestamp,
+        author: Option<Author>,
+    },
+    SetTrialConstraints {
+        trial_id: TrialId,
+        constraints: Vec<f64>,
+        timestamp: Timestamp,
+        author: Option<Author>,
     },
     SetTrialUserAttr {
         trial_id: TrialId,
         key: String,
         value: JsonValue,
         timestamp: Timestamp,
+        author: Option<Author>,
     },
     SetTrialSystemAttr {
         trial_id: TrialId,
         key: String,
         value: JsonValue,
         timestamp: Timestamp,
+        author: Option<Author>,
     },
 }
 impl Message {
+    /// Returns the identifier of the trial targeted by this message if it is a trial message.
+    pub fn trial_id(&self) -> Option<&TrialId> {
+        match self {
+            Message::CreateTrial { trial_id, .. }
+            | Message::SetTrialUserAttr { trial_id, .. }
+            | Message::SetTrialSystemAttr { trial_id, .. }
+            | Message::SetTrialParam { trial_id, .. }
+            | Message::SetTrialIntermediateValue { trial_id, .. }
+            | Message::SetTrialValue { trial_id, .. }
+            | Message::SetTrialConstraints { trial_id, .. }
+            | Message::SetTrialState { trial_id, .. } => Some(trial_id),
+            Message::SetStudyDirection { .. }
+            | Message::SetStudyUserAttr { .. }
+            | Message::SetStudySystemAttr { .. }
+            | Message::SetStudySampler { .. }
+            | Message::SetStudyPruner { .. }
+            | Message::SetStudySearchSpace { .. }
+            | Message::SetStudyTag { .. }
+            | Message::SetStudyAcl { .. }
+            | Message::SetStudyTrialBudget { .. }
+            | Message::SetStudyLifetime { .. }
+            | Message::SetStudyState { .. }
+            | Message::SetStudyWebhook { .. } => None,
+        }
+    }
+
+    /// Returns this message with the secrets in it (i.e., webhook URLs) redacted.
+    pub fn redacted(mut self) -> Self {
+        if let Message::SetStudyWebhook {
+            webhook: Some(webhook),
+            ..
+        } = &mut self
+        {
+            *webhook = webhook.redacted();
+        }
+        self
+    }
+
+    /// Returns the author of this message (`None` if it was issued by an older node).
+    pub fn author(&self) -> Option<&Author> {
+        match self {
+            Message::SetStudyDirection { author, .. }
+            | Message::CreateTrial { author, .. }
+            | Message::SetTrialUserAttr { author, .. }
+            | Message::SetTrialSystemAttr { author, .. }
+            | Message::SetTrialParam { author, .. }
+            | Message::SetTrialIntermediateValue { author, .. }
+            | Message::SetTrialValue { author, .. }
+            | Message::SetTrialConstraints { author, .. }
+            | Message::SetTrialState { author, .. }
+            | Message::SetStudyUserAttr { author, .. }
+            | Message::SetStudySystemAttr { author, .. }
+            | Message::SetStudySampler { author, .. }
+            | Message::SetStudyPruner { author, .. }
+            | Message::SetStudySearchSpace { author, .. }
+            | Message::SetStudyTag { author, .. }
+            | Message::SetStudyAcl { author, .. }
+            | Message::SetStudyTrialBudget { author, .. }
+            | Message::SetStudyLifetime { author, .. }
+            | Message::SetStudyState { author, .. }
+            | Message::SetStudyWebhook { author, .. } => author.as_ref(),
+        }
+    }
+
     pub fn timestamp(&self) -> Timestamp {
         match self {
             Message::SetStudyDirection { timestamp, .. }
@@ -68,9 +217,19 @@ impl Message {
             | Message::SetTrialParam { timestamp, .. }
             | Message::SetTrialIntermediateValue { timestamp, .. }
             | Message::SetTrialValue { timestamp, .. }
+            | Message::SetTrialConstraints { timestamp, .. }
             | Message::SetTrialState { timestamp, .. }
             | Message::SetStudyUserAttr { timestamp, .. }
-            | Message::SetStudySystemAttr { timestamp, .. } => *timestamp,
+            | Message::SetStudySystemAttr { timestamp, .. }
+            | Message::SetStudySampler { timestamp, .. }
+            | Message::SetStudyPruner { timestamp, .. }
+            | Message::SetStudySearchSpace { timestamp, .. }
+            | Message::SetStudyTag { timestamp, .. }
+            | Message::SetStudyAcl { timestamp, .. }
+            | Message::SetStudyTrialBudget { timestamp, .. }
+            | Message::SetStudyLifetime { timestamp, .. }
+            | Message::SetStudyState { timestamp, .. }
+            | Message::SetStudyWebhook { timestamp, .. } => *timestamp,
         }
     }
 }
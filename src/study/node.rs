@@ -1,57 +1,142 @@
+use crate::auth::{self, Acl, Permission};
+use crate::cluster_key::ClusterKey;
+use crate::distribution::Distribution;
+use crate::learning_curve::{self, LearningCurves};
+use crate::message::UnionMessage;
+use crate::pruner::{self, Pruner};
+use crate::rate_limit::{RateLimit, TokenBucket};
+use crate::sampler::{QmcIndex, QmcIndexAllocator, Sampler, QMC_INDEX_ATTR_KEY, QMC_SEED_ATTR_KEY};
+use crate::search_space::{SearchSpace, SearchSpaceMode, SearchSpaces};
 use crate::study::operation::{Operation, OperationKey};
+use crate::study::stats::{StatsCounter, StudyStats, TrialSnapshot};
 use crate::study::subscriber::{SubscribeId, Subscriber};
 use crate::study::{
-    Message, Seconds, StudyDirection, StudyId, StudyName, StudyNameAndId, StudySummary,
+    Author, Message, Namespace, Seconds, StudyDirection, StudyId, StudyLifetime, StudyName,
+    StudyNameAndId, StudyState, StudySummary, TrialBudget, ORIGIN_NAMESPACE_ATTR_KEY,
+    ORIGIN_STUDY_ID_ATTR_KEY, ORIGIN_STUDY_NAME_ATTR_KEY, ORIGIN_TRIAL_ID_ATTR_KEY,
 };
 use crate::time::Timestamp;
 use crate::trial::{Trial, TrialId, TrialParamValue, TrialState};
-use crate::{Error, PlumcastNode};
+use crate::warm_start::{TrialFilter, WARM_START_ATTR_KEY};
+use crate::webhook::{Webhook, WebhookEvent, WebhookSender};
+use crate::{Error, ErrorKind, PlumcastNode, Result};
+use atomic_immut::AtomicImmut;
 use fibers::sync::{mpsc, oneshot};
+use fibers::time::timer::{self, Timeout};
 use futures::{Async, Future, Poll, Stream};
 use plumcast::message::MessageId;
 use plumcast::node::NodeId;
-use serde_json::Value as JsonValue;
-use std::collections::HashMap;
-use std::time::Duration;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-const TIMEOUT_SEC: u64 = 60 * 60; // TODO:
+/// The maximum number of write messages kept in the audit log of a study.
+const AUDIT_LOG_CAPACITY: usize = 1000;
+
+/// The number of messages waiting for the broadcast limit above which write requests to the study are rejected.
+const MAX_PENDING_BROADCASTS: usize = 1000;
 
 #[derive(Debug)]
 pub struct StudyNode {
+    namespace: Namespace,
     study_name: StudyName,
     study_id: StudyId,
     direction: StudyDirection,
+    state: Arc<AtomicImmut<StudyState>>,
     user_attrs: HashMap<String, JsonValue>,
     system_attrs: HashMap<String, JsonValue>,
+    sampler: Option<Sampler>,
+    pruner: Option<Pruner>,
+    search_space: Option<SearchSpace>,
+    tags: BTreeMap<String, String>,
+    acl: Arc<AtomicImmut<Acl>>,
+    webhooks: BTreeMap<String, Webhook>,
+    webhook_sender: WebhookSender,
+    trial_budget: Option<TrialBudget>,
+    lifetime: Option<StudyLifetime>,
+    default_lifetime: StudyLifetime,
+    unapplied_trials: HashSet<TrialId>,
     trials: HashMap<TrialId, Trial>,
+    param_distributions: HashMap<String, Distribution>,
+    search_spaces: SearchSpaces,
+    stats: StatsCounter,
+    audit_log: VecDeque<Message>,
+    broadcast_bucket: Option<TokenBucket>,
+    pending_broadcasts: VecDeque<Message>,
+    n_pending_broadcasts: Arc<AtomicUsize>,
+    broadcast_timeout: Option<Timeout>,
     datetime_start: Seconds,
     inner: PlumcastNode,
     command_tx: mpsc::Sender<Command>,
     command_rx: mpsc::Receiver<Command>,
     operations: HashMap<OperationKey, Operation>,
-    expiry_time: Duration,
+    last_activity_time: Duration,
     next_subscribe_id: SubscribeId,
     subscribers: HashMap<SubscribeId, Subscriber>,
+    qmc_index_allocator: QmcIndexAllocator,
+    cluster_key: Option<ClusterKey>,
 }
 impl StudyNode {
-    pub fn new(study: StudyNameAndId, inner: PlumcastNode) -> Self {
-        let expiry_time = inner.clock().now().as_duration() + Duration::from_secs(TIMEOUT_SEC);
+    /// Makes a new `StudyNode` instance.
+    ///
+    /// If `broadcast_limit` is given, the messages exceeding the limit are queued and broadcast later
+    /// (see `StudyNodeHandle::check_pending_broadcasts`).
+    ///
+    /// `default_lifetime` is used unless a lifetime is set to the study.
+    ///
+    /// If `cluster_key` is given, the messages of the study are signed and verified with the key.
+    pub fn new(
+        study: StudyNameAndId,
+        inner: PlumcastNode,
+        broadcast_limit: Option<RateLimit>,
+        default_lifetime: StudyLifetime,
+        webhook_sender: WebhookSender,
+        cluster_key: Option<ClusterKey>,
+    ) -> Self {
+        let last_activity_time = inner.clock().now().as_duration();
         let (command_tx, command_rx) = mpsc::channel();
+        let qmc_index_allocator = QmcIndexAllocator::new(inner.id());
         StudyNode {
+            namespace: study.namespace,
             study_name: study.study_name,
             study_id: study.study_id,
             direction: StudyDirection::NotSet,
+            state: Default::default(),
             user_attrs: HashMap::new(),
             system_attrs: HashMap::new(),
+            sampler: None,
+            pruner: None,
+            search_space: None,
+            tags: BTreeMap::new(),
+            acl: Default::default(),
+            webhooks: BTreeMap::new(),
+            webhook_sender,
+            trial_budget: None,
+            lifetime: None,
+            default_lifetime,
+            unapplied_trials: HashSet::new(),
             trials: HashMap::new(),
+            param_distributions: HashMap::new(),
+            search_spaces: SearchSpaces::new(),
+            stats: StatsCounter::new(),
+            audit_log: VecDeque::new(),
+            broadcast_bucket: broadcast_limit.map(|limit| TokenBucket::new(limit, Instant::now())),
+            pending_broadcasts: VecDeque::new(),
+            n_pending_broadcasts: Arc::default(),
+            broadcast_timeout: None,
             datetime_start: Seconds::now(),
             inner,
             command_tx,
             command_rx,
             operations: HashMap::new(),
-            expiry_time,
+            last_activity_time,
             next_subscribe_id: SubscribeId::new(),
             subscribers: HashMap::new(),
+            qmc_index_allocator,
+            cluster_key,
         }
     }
 
@@ -59,22 +144,82 @@ impl StudyNode {
         StudyNodeHandle {
             command_tx: self.command_tx.clone(),
             node_id: self.inner.id(),
+            acl: Arc::clone(&self.acl),
+            state: Arc::clone(&self.state),
+            n_pending_broadcasts: Arc::clone(&self.n_pending_broadcasts),
+            client: None,
+            verified: false,
+        }
+    }
+
+    fn broadcast(&mut self, message: Message) {
+        self.pending_broadcasts.push_back(message);
+        self.flush_broadcasts();
+    }
+
+    /// Broadcasts the pending messages as far as the broadcast limit allows.
+    fn flush_broadcasts(&mut self) {
+        let now = Instant::now();
+        while !self.pending_broadcasts.is_empty() {
+            if let Some(bucket) = self.broadcast_bucket.as_mut() {
+                if !bucket.try_acquire(now) {
+                    if self.broadcast_timeout.is_none() {
+                        self.broadcast_timeout = Some(timer::timeout(bucket.wait_time()));
+                    }
+                    break;
+                }
+            }
+            let message = self.pending_broadcasts.pop_front().expect("never fails");
+            let message = UnionMessage::from(message).sign(self.cluster_key.as_ref());
+            self.inner.broadcast(message);
         }
+        self.n_pending_broadcasts
+            .store(self.pending_broadcasts.len(), atomic::Ordering::SeqCst);
     }
 
     fn now(&self) -> Duration {
         self.inner.clock().now().as_duration()
     }
 
+    /// Returns the time at which the study expires on this node (`None` if it never expires).
+    fn expiry_time(&self) -> Option<Duration> {
+        let lifetime = self.lifetime.unwrap_or(self.default_lifetime);
+        lifetime.as_duration().map(|d| self.last_activity_time + d)
+    }
+
     fn handle_message(&mut self, mid: MessageId, message: Message) {
         if !self.check_message(mid, &message) {
             return;
         }
 
+        let redacted = message.clone().redacted();
         for s in self.subscribers.values_mut() {
-            s.push_message(message.clone());
+            s.push_message(redacted.clone());
+        }
+        if self.audit_log.len() == AUDIT_LOG_CAPACITY {
+            self.audit_log.pop_front();
         }
+        self.audit_log.push_back(redacted);
 
+        let trial_id = message.trial_id().cloned();
+        let before = trial_id
+            .as_ref()
+            .and_then(|id| self.trials.get(id))
+            .and_then(TrialSnapshot::new);
+
+        // Only the node where the message originated delivers the webhook notifications.
+        let notifies = mid.node() == self.inner.id() && !self.webhooks.is_empty();
+        let author = if notifies {
+            message.author().cloned()
+        } else {
+            None
+        };
+        let best_before = if notifies { self.best_trial_id() } else { None };
+        let trial_state_before = trial_id
+            .as_ref()
+            .and_then(|id| self.trials.get(id))
+            .and_then(|t| t.visible_state());
+        let mut events = Vec::new();
         match message {
             Message::SetStudyDirection { direction, .. } => {
                 log::debug!("Set study direction: {:?}", direction);
@@ -86,18 +231,86 @@ impl StudyNode {
             Message::SetStudySystemAttr { key, value, .. } => {
                 self.system_attrs.insert(key, value);
             }
+            Message::SetStudySampler { sampler, .. } => {
+                self.sampler = Some(sampler);
+            }
+            Message::SetStudyPruner { pruner, .. } => {
+                self.pruner = Some(pruner);
+            }
+            Message::SetStudySearchSpace { search_space, .. } => {
+                self.search_space = Some(search_space);
+            }
+            Message::SetStudyAcl {
+                principal,
+                permission,
+                ..
+            } => {
+                self.acl.update(|acl| {
+                    let mut acl = acl.clone();
+                    if let Some(permission) = permission {
+                        acl.insert(principal.clone(), permission);
+                    } else {
+                        acl.remove(&principal);
+                    }
+                    acl
+                });
+            }
+            Message::SetStudyTrialBudget { budget, .. } => {
+                self.trial_budget = budget;
+            }
+            Message::SetStudyLifetime { lifetime, .. } => {
+                self.lifetime = lifetime;
+            }
+            Message::SetStudyState { state, .. } => {
+                self.state.store(state);
+                events.push(WebhookEvent::StudyStateChanged);
+            }
+            Message::SetStudyWebhook { name, webhook, .. } => {
+                if let Some(webhook) = webhook {
+                    self.webhooks.insert(name, webhook);
+                } else {
+                    self.webhooks.remove(&name);
+                }
+            }
+            Message::SetStudyTag { key, value, .. } => {
+                if let Some(value) = value {
+                    self.tags.insert(key, value);
+                } else {
+                    self.tags.remove(&key);
+                }
+            }
             Message::CreateTrial {
                 trial_id,
                 timestamp,
+                ..
             } => {
+                self.unapplied_trials.remove(&trial_id);
                 self.get_trial_mut(trial_id).datetime_start = Some(timestamp.to_seconds());
+                let overshoot = self.trial_budget_overshoot();
+                if overshoot > 0 {
+                    log::warn!(
+                        "Study {:?} exceeds the trial budget by {} trial(s)",
+                        self.study_name,
+                        overshoot
+                    );
+                }
+                if let Some(budget) = self.trial_budget {
+                    let applied = self.count_budgeted_trials(budget) - self.unapplied_trials.len();
+                    if applied == budget.max_trials as usize {
+                        events.push(WebhookEvent::TrialBudgetExhausted);
+                    }
+                }
             }
             Message::SetTrialState {
                 trial_id,
                 state,
                 timestamp,
+                ..
             } => {
-                self.get_trial_mut(trial_id).set_state(state, timestamp);
+                self.get_trial_mut(trial_id.clone())
+                    .set_state(state, timestamp);
+                self.search_spaces
+                    .handle_trial_update(&self.trials[&trial_id]);
             }
             Message::SetTrialParam {
                 trial_id,
@@ -105,13 +318,27 @@ impl StudyNode {
                 value,
                 ..
             } => {
-                self.get_trial_mut(trial_id).params.insert(key, value);
+                self.param_distributions
+                    .entry(key.clone())
+                    .or_insert_with(|| value.distribution.clone());
+                self.get_trial_mut(trial_id.clone())
+                    .params
+                    .insert(key, value);
+                self.search_spaces
+                    .handle_trial_update(&self.trials[&trial_id]);
             }
             Message::SetTrialValue {
                 trial_id, value, ..
             } => {
                 self.get_trial_mut(trial_id).value = Some(value);
             }
+            Message::SetTrialConstraints {
+                trial_id,
+                constraints,
+                ..
+            } => {
+                self.get_trial_mut(trial_id).constraints = Some(constraints);
+            }
             Message::SetTrialIntermediateValue {
                 trial_id,
                 step,
@@ -139,6 +366,65 @@ impl StudyNode {
                 self.get_trial_mut(trial_id).system_attrs.insert(key, value);
             }
         }
+        if let Some(trial_id) = &trial_id {
+            let after = TrialSnapshot::new(&self.trials[trial_id]);
+            self.stats.update(before, after);
+        }
+
+        if !notifies {
+            return;
+        }
+        if let Some(trial) = trial_id.as_ref().and_then(|id| self.trials.get(id)) {
+            let trial_state = trial.visible_state();
+            if trial_state != trial_state_before {
+                match trial_state {
+                    Some(TrialState::Complete) => events.push(WebhookEvent::TrialCompleted),
+                    Some(TrialState::Fail) => events.push(WebhookEvent::TrialFailed),
+                    _ => {}
+                }
+            }
+            let best_after = self.best_trial_id();
+            if best_after != best_before && best_after.as_ref() == Some(&trial.trial_id) {
+                events.push(WebhookEvent::NewBestTrial);
+            }
+        }
+        for event in events {
+            self.notify_webhooks(event, trial_id.as_ref(), author.as_ref());
+        }
+    }
+
+    fn best_trial_id(&self) -> Option<TrialId> {
+        self.best_trials().into_iter().next().map(|t| t.trial_id)
+    }
+
+    fn notify_webhooks(
+        &self,
+        event: WebhookEvent,
+        trial_id: Option<&TrialId>,
+        author: Option<&Author>,
+    ) {
+        let trial = trial_id.and_then(|id| self.trials.get(id));
+        let variables = vec![
+            ("event", to_json(&event)),
+            ("study_id", to_json(&self.study_id)),
+            ("namespace", to_json(&self.namespace)),
+            ("study_name", to_json(&self.study_name)),
+            ("study_state", to_json(&*self.state.load())),
+            ("trial_id", to_json(&trial_id)),
+            (
+                "trial_state",
+                to_json(&trial.and_then(|t| t.visible_state())),
+            ),
+            ("value", to_json(&trial.and_then(|t| t.value))),
+            ("client", to_json(&author.and_then(|a| a.client.as_ref()))),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_owned(), v))
+        .collect::<JsonMap<_, _>>();
+        for webhook in self.webhooks.values().filter(|w| w.accepts(event)) {
+            self.webhook_sender
+                .send(webhook, &webhook.render(&variables));
+        }
     }
 
     fn get_trial_mut(&mut self, trial_id: TrialId) -> &mut Trial {
@@ -147,6 +433,371 @@ impl StudyNode {
             .or_insert_with(|| Trial::new(trial_id))
     }
 
+    fn validate_param(&self, key: &str, value: &TrialParamValue) -> Result<()> {
+        track!(value.distribution.validate(); key)?;
+        track_assert!(
+            value.distribution.contains(value.value),
+            ErrorKind::InvalidInput,
+            "Out of range parameter value: key={:?}, value={:?}",
+            key,
+            value
+        );
+        if let Some(declared) = self.search_space.as_ref().and_then(|s| s.get(key)) {
+            track!(declared.check_compatibility(&value.distribution); key)?;
+        }
+        if let Some(registered) = self.param_distributions.get(key) {
+            track!(registered.check_compatibility(&value.distribution); key)?;
+        }
+        Ok(())
+    }
+
+    fn set_trial_param(
+        &mut self,
+        trial_id: TrialId,
+        key: String,
+        value: TrialParamValue,
+        author: Option<Author>,
+    ) -> Result<()> {
+        track!(self.validate_param(&key, &value))?;
+        let message = Message::SetTrialParam {
+            trial_id,
+            key,
+            value,
+            timestamp: Timestamp::now(),
+            author: author.clone(),
+        };
+        self.broadcast(message);
+        Ok(())
+    }
+
+    fn sample_params(
+        &mut self,
+        trial_id: TrialId,
+        search_space: &BTreeMap<String, Distribution>,
+        author: Option<Author>,
+    ) -> Result<HashMap<String, TrialParamValue>> {
+        let sampler = track_assert_some!(
+            self.sampler.clone(),
+            ErrorKind::InvalidInput,
+            "No sampler is configured"
+        );
+        let trial = track_assert_some!(
+            self.trials
+                .get(&trial_id)
+                .filter(|t| t.datetime_start.is_some()),
+            ErrorKind::NotFound
+        );
+
+        for (key, distribution) in search_space {
+            track!(distribution.validate(); key)?;
+            if let Some(declared) = self.search_space.as_ref().and_then(|s| s.get(key)) {
+                track!(declared.check_compatibility(distribution); key)?;
+            }
+            if let Some(registered) = self.param_distributions.get(key) {
+                track!(registered.check_compatibility(distribution); key)?;
+            }
+        }
+
+        let attr = |key| trial.system_attrs.get(key).and_then(|v| v.as_u64());
+        let allocated_index = match (attr(QMC_SEED_ATTR_KEY), attr(QMC_INDEX_ATTR_KEY)) {
+            (Some(seed), Some(position)) => Some(QmcIndex { seed, position }),
+            _ => None,
+        };
+        let index = allocated_index.unwrap_or_else(|| self.qmc_index_allocator.allocate());
+        let mut params = track!(sampler.sample(index, search_space))?;
+        if allocated_index.is_none() {
+            for (key, value) in &[
+                (QMC_SEED_ATTR_KEY, index.seed),
+                (QMC_INDEX_ATTR_KEY, index.position),
+            ] {
+                let message = Message::SetTrialSystemAttr {
+                    trial_id: trial_id.clone(),
+                    key: (*key).to_owned(),
+                    value: (*value).into(),
+                    timestamp: Timestamp::now(),
+                    author: author.clone(),
+                };
+                self.broadcast(message);
+            }
+        }
+        for (key, value) in &mut params {
+            let message = Message::SetTrialParam {
+                trial_id: trial_id.clone(),
+                key: key.clone(),
+                value: value.clone(),
+                timestamp: Timestamp::now(),
+                author: author.clone(),
+            };
+            self.broadcast(message);
+            value.fill_external_value();
+        }
+        Ok(params)
+    }
+
+    /// Creates a new trial unless the trial budget of this study is exhausted.
+    fn create_trial(&mut self, trial_id: TrialId, author: Option<Author>) -> Result<()> {
+        let state = *self.state.load();
+        track_assert!(
+            state.accepts_new_trials(),
+            ErrorKind::InvalidState,
+            "No new trials can be created in the {:?} study",
+            state
+        );
+        if let Some(budget) = self.trial_budget {
+            let n = self.count_budgeted_trials(budget);
+            track_assert!(
+                n < budget.max_trials as usize,
+                ErrorKind::LimitExceeded,
+                "The trial budget of the study is exhausted: trials={}, max_trials={}",
+                n,
+                budget.max_trials
+            );
+        }
+        self.unapplied_trials.insert(trial_id.clone());
+        let message = Message::CreateTrial {
+            trial_id,
+            timestamp: Timestamp::now(),
+            author,
+        };
+        self.broadcast(message);
+        Ok(())
+    }
+
+    /// Returns the number of the trials counted against `budget` (including the ones being created by this node).
+    fn count_budgeted_trials(&self, budget: TrialBudget) -> usize {
+        let created = self
+            .trials
+            .values()
+            .filter(|t| t.datetime_start.is_some())
+            .filter(|t| !(budget.exclude_failed && t.visible_state() == Some(TrialState::Fail)))
+            .count();
+        created + self.unapplied_trials.len()
+    }
+
+    fn trial_budget_overshoot(&self) -> usize {
+        self.trial_budget.map_or(0, |budget| {
+            self.count_budgeted_trials(budget)
+                .saturating_sub(budget.max_trials as usize)
+        })
+    }
+
+    fn should_prune(&mut self, trial_id: TrialId, author: Option<Author>) -> Result<bool> {
+        let pruner = track_assert_some!(
+            self.pruner.as_ref(),
+            ErrorKind::InvalidInput,
+            "No pruner is configured"
+        );
+        let trial = track_assert_some!(
+            self.trials.get(&trial_id).and_then(|t| t.adjust()),
+            ErrorKind::NotFound
+        );
+        let trials = self
+            .trials
+            .values()
+            .filter_map(|t| t.adjust())
+            .collect::<Vec<_>>();
+        let decision = pruner.should_prune(self.direction, &trial, &trials);
+        for (key, value) in decision.system_attrs {
+            let message = Message::SetTrialSystemAttr {
+                trial_id: trial_id.clone(),
+                key,
+                value,
+                timestamp: Timestamp::now(),
+                author: author.clone(),
+            };
+            self.broadcast(message);
+        }
+        Ok(decision.prune)
+    }
+
+    /// Returns the completed trials ordered from the best.
+    ///
+    /// Feasible trials always precede infeasible ones.
+    fn best_trials(&self) -> Vec<Trial> {
+        let mut trials = self
+            .trials
+            .values()
+            .filter_map(|t| t.adjust())
+            .filter(|t| t.is_complete())
+            .filter(|t| t.value.is_some_and(|v| !v.is_nan()))
+            .collect::<Vec<_>>();
+        let direction = self.direction;
+        trials.sort_by(|a, b| {
+            b.is_feasible().cmp(&a.is_feasible()).then_with(|| {
+                let ordering = a.value.partial_cmp(&b.value).unwrap_or(Ordering::Equal);
+                if direction == StudyDirection::Maximize {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+        });
+        trials
+    }
+
+    /// Makes the messages that reproduce this study (and its completed trials) in the study `study_id`.
+    ///
+    /// The copied trials are given new identifiers and the origins are recorded in their system attributes.
+    fn clone_messages(&self, study_id: &StudyId, author: Option<Author>) -> Vec<Message> {
+        let timestamp = Timestamp::now();
+        let mut messages = vec![Message::SetStudyDirection {
+            direction: self.direction,
+            timestamp,
+            author: author.clone(),
+        }];
+        for (key, value) in &self.user_attrs {
+            messages.push(Message::SetStudyUserAttr {
+                key: key.clone(),
+                value: value.clone(),
+                timestamp,
+                author: author.clone(),
+            });
+        }
+        for (key, value) in &self.system_attrs {
+            messages.push(Message::SetStudySystemAttr {
+                key: key.clone(),
+                value: value.clone(),
+                timestamp,
+                author: author.clone(),
+            });
+        }
+        messages.push(Message::SetStudySystemAttr {
+            key: ORIGIN_STUDY_ID_ATTR_KEY.to_owned(),
+            value: self.study_id.as_uuid().to_string().into(),
+            timestamp,
+            author: author.clone(),
+        });
+        messages.push(Message::SetStudySystemAttr {
+            key: ORIGIN_NAMESPACE_ATTR_KEY.to_owned(),
+            value: self.namespace.as_str().into(),
+            timestamp,
+            author: author.clone(),
+        });
+        messages.push(Message::SetStudySystemAttr {
+            key: ORIGIN_STUDY_NAME_ATTR_KEY.to_owned(),
+            value: self.study_name.as_str().into(),
+            timestamp,
+            author: author.clone(),
+        });
+        if let Some(budget) = self.trial_budget {
+            messages.push(Message::SetStudyTrialBudget {
+                budget: Some(budget),
+                timestamp,
+                author: author.clone(),
+            });
+        }
+        if let Some(lifetime) = self.lifetime {
+            messages.push(Message::SetStudyLifetime {
+                lifetime: Some(lifetime),
+                timestamp,
+                author: author.clone(),
+            });
+        }
+        for (key, value) in &self.tags {
+            messages.push(Message::SetStudyTag {
+                key: key.clone(),
+                value: Some(value.clone()),
+                timestamp,
+                author: author.clone(),
+            });
+        }
+        if let Some(sampler) = self.sampler.clone() {
+            messages.push(Message::SetStudySampler {
+                sampler,
+                timestamp,
+                author: author.clone(),
+            });
+        }
+        if let Some(pruner) = self.pruner.clone() {
+            messages.push(Message::SetStudyPruner {
+                pruner,
+                timestamp,
+                author: author.clone(),
+            });
+        }
+        if let Some(search_space) = self.search_space.clone() {
+            messages.push(Message::SetStudySearchSpace {
+                search_space,
+                timestamp,
+                author: author.clone(),
+            });
+        }
+
+        let trials = self
+            .trials
+            .values()
+            .filter(|t| t.visible_state() == Some(TrialState::Complete));
+        for trial in trials {
+            let trial_id = TrialId::new(study_id);
+            messages.extend(copy_trial_messages(
+                trial,
+                trial_id,
+                ORIGIN_TRIAL_ID_ATTR_KEY,
+                timestamp,
+                author.clone(),
+            ));
+        }
+        messages
+    }
+
+    /// Imports the completed trials selected from other studies as new trials.
+    ///
+    /// Parameters that are incompatible with this study are dropped.
+    /// Imports the complete trials in `trials` unless the study is closed to new trials
+    /// or they exceed the trial budget of the study.
+    fn import_trials(
+        &mut self,
+        trials: Vec<Trial>,
+        author: Option<Author>,
+    ) -> Result<Vec<TrialId>> {
+        let state = *self.state.load();
+        track_assert!(
+            state.accepts_new_trials(),
+            ErrorKind::InvalidState,
+            "No trials can be imported into the {:?} study",
+            state
+        );
+        let trials = trials
+            .into_iter()
+            .filter(|t| t.is_complete())
+            .collect::<Vec<_>>();
+        if let Some(budget) = self.trial_budget {
+            let n = self.count_budgeted_trials(budget);
+            track_assert!(
+                n + trials.len() <= budget.max_trials as usize,
+                ErrorKind::LimitExceeded,
+                "The trial budget of the study is exhausted: trials={}, imports={}, max_trials={}",
+                n,
+                trials.len(),
+                budget.max_trials
+            );
+        }
+
+        let timestamp = Timestamp::now();
+        let mut trial_ids = Vec::new();
+        for mut trial in trials {
+            let params = std::mem::take(&mut trial.params);
+            trial.params = params
+                .into_iter()
+                .filter(|(key, value)| self.validate_param(key, value).is_ok())
+                .collect();
+
+            let trial_id = TrialId::new(&self.study_id);
+            self.unapplied_trials.insert(trial_id.clone());
+            for message in copy_trial_messages(
+                &trial,
+                trial_id.clone(),
+                WARM_START_ATTR_KEY,
+                timestamp,
+                author.clone(),
+            ) {
+                self.broadcast(message);
+            }
+            trial_ids.push(trial_id);
+        }
+        Ok(trial_ids)
+    }
+
     fn check_message(&mut self, mid: MessageId, message: &Message) -> bool {
         let key = OperationKey::from_message(message);
         let op = Operation::new(mid, message);
@@ -171,27 +822,38 @@ impl StudyNode {
     }
 
     fn handle_command(&mut self, command: Command) {
-        self.expiry_time =
-            self.inner.clock().now().as_duration() + Duration::from_secs(TIMEOUT_SEC);
+        if command.extends_lifetime() {
+            self.last_activity_time = self.now();
+        }
         match command {
             Command::GetSummary { reply_tx } => {
-                // TODO: regard direction
-                let best_trial = self
-                    .trials
-                    .values()
-                    .filter(|t| t.is_complete())
-                    .filter(|t| t.value.map_or(false, |v| !v.is_nan()))
-                    .min_by(|a, b| a.value.partial_cmp(&b.value).expect("never fails"))
-                    .cloned();
+                let best_trial = self.best_trials().into_iter().next();
+                let constrained = self.trials.values().filter(|t| t.constraints.is_some());
+                let n_feasible_trials = constrained.clone().filter(|t| t.is_feasible()).count();
+                let n_infeasible_trials = constrained.count() - n_feasible_trials;
                 let summary = StudySummary {
                     study_id: self.study_id.clone(),
+                    namespace: self.namespace.clone(),
                     study_name: self.study_name.clone(),
                     direction: self.direction,
+                    state: *self.state.load(),
                     user_attrs: self.user_attrs.clone(),
                     system_attrs: self.system_attrs.clone(),
+                    sampler: self.sampler.clone(),
+                    pruner: self.pruner.clone(),
+                    search_space: self.search_space.clone(),
+                    tags: self.tags.clone(),
                     best_trial,
                     n_trials: self.trials.len() as u32,
+                    n_feasible_trials: n_feasible_trials as u32,
+                    n_infeasible_trials: n_infeasible_trials as u32,
                     datetime_start: self.datetime_start,
+                    trial_budget: self.trial_budget,
+                    trial_budget_overshoot: self.trial_budget_overshoot() as u32,
+                    lifetime: self.lifetime,
+                    remaining_lifetime_sec: self
+                        .expiry_time()
+                        .map(|t| t.saturating_sub(self.now()).as_secs()),
                 };
                 reply_tx.exit(Ok(summary));
             }
@@ -206,11 +868,109 @@ impl StudyNode {
                 let trials = self.trials.values().filter_map(|t| t.adjust()).collect();
                 reply_tx.exit(Ok(trials));
             }
+            Command::CloneMessages {
+                study_id,
+                author,
+                reply_tx,
+            } => {
+                reply_tx.exit(Ok(self.clone_messages(&study_id, author)));
+            }
+            Command::SelectTrials { filter, reply_tx } => {
+                let trials = self.trials.values().filter_map(|t| t.adjust()).collect();
+                reply_tx.exit(Ok(filter.select(trials)));
+            }
+            Command::ImportTrials {
+                trials,
+                author,
+                reply_tx,
+            } => {
+                reply_tx.exit(track!(self.import_trials(trials, author)));
+            }
+            Command::GetStats { reply_tx } => {
+                reply_tx.exit(Ok(self.stats.stats(Seconds::now())));
+            }
+            Command::GetLearningCurves {
+                states,
+                percentiles,
+                reply_tx,
+            } => {
+                let trials = self.trials.values().filter(|t| {
+                    t.visible_state()
+                        .is_some_and(|state| states.as_ref().is_none_or(|s| s.contains(&state)))
+                });
+                let curves = learning_curve::aggregate(self.direction, trials, &percentiles);
+                reply_tx.exit(Ok(curves));
+            }
+            Command::GetWebhooks { reply_tx } => {
+                reply_tx.exit(Ok(self.webhooks.clone()));
+            }
+            Command::GetAuditLog {
+                trial_id,
+                limit,
+                reply_tx,
+            } => {
+                let messages = self
+                    .audit_log
+                    .iter()
+                    .rev()
+                    .filter(|m| trial_id.is_none() || m.trial_id() == trial_id.as_ref())
+                    .take(limit)
+                    .cloned()
+                    .collect();
+                reply_tx.exit(Ok(messages));
+            }
+            Command::GetBestTrials { k, reply_tx } => {
+                let mut trials = self.best_trials();
+                trials.truncate(k);
+                reply_tx.exit(Ok(trials));
+            }
+            Command::SampleParams {
+                trial_id,
+                search_space,
+                author,
+                reply_tx,
+            } => {
+                let result = track!(self.sample_params(trial_id, &search_space, author));
+                reply_tx.exit(result);
+            }
+            Command::GetSearchSpace { mode, reply_tx } => {
+                let search_space = self.search_spaces.get(mode, self.trials.values());
+                reply_tx.exit(Ok(search_space));
+            }
+            Command::SetTrialParam {
+                trial_id,
+                key,
+                value,
+                author,
+                reply_tx,
+            } => {
+                let result = track!(self.set_trial_param(trial_id, key, value, author));
+                reply_tx.exit(result);
+            }
+            Command::CreateTrial {
+                trial_id,
+                author,
+                reply_tx,
+            } => {
+                let result = track!(self.create_trial(trial_id, author));
+                reply_tx.exit(result);
+            }
+            Command::ShouldPrune {
+                trial_id,
+                author,
+                reply_tx,
+            } => {
+                let result = track!(self.should_prune(trial_id, author));
+                reply_tx.exit(result);
+            }
             Command::Subscribe { reply_tx } => {
                 let subscribe_id = self.next_subscribe_id.next();
                 let mut s = Subscriber::new(self.now());
                 for m in self.inner.plumtree_node().messages() {
-                    s.push_message(m.1.clone().into_study_message().expect("never fails"));
+                    let message = m.1.clone().reopen(self.cluster_key.as_ref());
+                    if let Ok(message) = message.and_then(|m| m.into_study_message()) {
+                        s.push_message(message.redacted());
+                    }
                 }
                 self.subscribers.insert(subscribe_id, s);
                 reply_tx.exit(Ok(subscribe_id));
@@ -232,8 +992,9 @@ impl StudyNode {
                 }
             }
             Command::Broadcast { message } => {
-                self.inner.broadcast(message.into());
+                self.broadcast(message);
             }
+            Command::KeepAlive => {}
         }
     }
 }
@@ -248,16 +1009,31 @@ impl Future for StudyNode {
 
             while let Async::Ready(Some(message)) = track!(self.inner.poll())? {
                 did_something = true;
-                let id = message.id().clone();
-                let payload = track!(message.into_payload().into_study_message())?;
+                let id = *message.id();
+                let payload = message.into_payload();
+                let payload = match track!(payload.verify(self.cluster_key.as_ref())) {
+                    Ok(payload) => track!(payload.into_study_message())?,
+                    Err(e) => {
+                        log::warn!("Drops an unverified message: {}", e);
+                        self.inner.forget_message(&id);
+                        continue;
+                    }
+                };
                 self.handle_message(id, payload);
             }
             while let Async::Ready(Some(command)) = self.command_rx.poll().expect("never fails") {
                 did_something = true;
                 self.handle_command(command);
             }
+            if let Some(timeout) = self.broadcast_timeout.as_mut() {
+                if track!(timeout.poll().map_err(Error::from))?.is_ready() {
+                    did_something = true;
+                    self.broadcast_timeout = None;
+                    self.flush_broadcasts();
+                }
+            }
 
-            if self.expiry_time < self.inner.clock().now().as_duration() {
+            if self.expiry_time().is_some_and(|t| t < self.now()) {
                 log::info!("Study timeout");
                 return Ok(Async::Ready(()));
             }
@@ -283,12 +1059,94 @@ impl Future for StudyNode {
 pub struct StudyNodeHandle {
     command_tx: mpsc::Sender<Command>,
     node_id: NodeId,
+    acl: Arc<AtomicImmut<Acl>>,
+    state: Arc<AtomicImmut<StudyState>>,
+    n_pending_broadcasts: Arc<AtomicUsize>,
+    client: Option<String>,
+    verified: bool,
 }
 impl StudyNodeHandle {
     pub fn node_id(&self) -> NodeId {
         self.node_id
     }
 
+    /// Returns a handle that records `client` as the author of the writes issued through it.
+    ///
+    /// `verified` indicates whether `client` is the principal of an authenticated token.
+    pub fn with_client(mut self, client: Option<String>, verified: bool) -> Self {
+        self.client = client;
+        self.verified = verified;
+        self
+    }
+
+    fn author(&self) -> Option<Author> {
+        Some(Author {
+            node: self.node_id,
+            client: self.client.clone(),
+            verified: self.verified,
+        })
+    }
+
+    pub fn acl(&self) -> Acl {
+        (*self.acl.load()).clone()
+    }
+
+    /// Checks whether `principal` has `permission` on this study.
+    pub fn authorize(&self, principal: Option<&str>, permission: Permission) -> Result<()> {
+        track!(auth::check_acl(&self.acl.load(), principal, permission))
+    }
+
+    pub fn state(&self) -> StudyState {
+        *self.state.load()
+    }
+
+    /// Checks whether the messages of this study waiting for the broadcast limit are not too many.
+    ///
+    /// This keeps the queue of them (and thus the delay of the later writes) bounded.
+    pub fn check_pending_broadcasts(&self) -> Result<()> {
+        let n = self.n_pending_broadcasts.load(atomic::Ordering::SeqCst);
+        track_assert!(
+            n < MAX_PENDING_BROADCASTS,
+            ErrorKind::RateLimited,
+            "Too many messages of the study are waiting to be broadcast: {}",
+            n
+        );
+        Ok(())
+    }
+
+    /// Checks whether the trials and settings of this study can be modified.
+    pub fn check_writable(&self) -> Result<()> {
+        let state = self.state();
+        track_assert!(
+            state.is_writable(),
+            ErrorKind::InvalidState,
+            "The study is {:?}",
+            state
+        );
+        Ok(())
+    }
+
+    pub fn set_study_state(&self, state: StudyState) {
+        let message = Message::SetStudyState {
+            state,
+            timestamp: Timestamp::now(),
+            author: self.author(),
+        };
+        let command = Command::Broadcast { message };
+        let _ = self.command_tx.send(command);
+    }
+
+    pub fn set_study_acl(&self, principal: String, permission: Option<Permission>) {
+        let message = Message::SetStudyAcl {
+            principal,
+            permission,
+            timestamp: Timestamp::now(),
+            author: self.author(),
+        };
+        let command = Command::Broadcast { message };
+        let _ = self.command_tx.send(command);
+    }
+
     pub fn get_summary(&self) -> impl Future<Item = StudySummary, Error = Error> {
         let (reply_tx, reply_rx) = oneshot::monitor();
         let command = Command::GetSummary { reply_tx };
@@ -310,10 +1168,103 @@ impl StudyNodeHandle {
         track_err!(reply_rx.map_err(Error::from))
     }
 
+    pub fn clone_messages(
+        &self,
+        study_id: StudyId,
+    ) -> impl Future<Item = Vec<Message>, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::CloneMessages {
+            study_id,
+            author: self.author(),
+            reply_tx,
+        };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    pub fn broadcast_messages(&self, messages: Vec<Message>) {
+        for message in messages {
+            let command = Command::Broadcast { message };
+            let _ = self.command_tx.send(command);
+        }
+    }
+
+    pub fn select_trials(
+        &self,
+        filter: TrialFilter,
+    ) -> impl Future<Item = Vec<Trial>, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::SelectTrials { filter, reply_tx };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    pub fn import_trials(
+        &self,
+        trials: Vec<Trial>,
+    ) -> impl Future<Item = Vec<TrialId>, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::ImportTrials {
+            trials,
+            author: self.author(),
+            reply_tx,
+        };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    pub fn get_stats(&self) -> impl Future<Item = StudyStats, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::GetStats { reply_tx };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    pub fn get_learning_curves(
+        &self,
+        states: Option<Vec<TrialState>>,
+        percentiles: Vec<f64>,
+    ) -> impl Future<Item = LearningCurves, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::GetLearningCurves {
+            states,
+            percentiles,
+            reply_tx,
+        };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    /// Returns the most recent `limit` write messages applied to this study (newest first).
+    ///
+    /// If `trial_id` is given, only the messages targeting the trial are returned.
+    pub fn get_audit_log(
+        &self,
+        trial_id: Option<TrialId>,
+        limit: usize,
+    ) -> impl Future<Item = Vec<Message>, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::GetAuditLog {
+            trial_id,
+            limit,
+            reply_tx,
+        };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    pub fn get_best_trials(&self, k: usize) -> impl Future<Item = Vec<Trial>, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::GetBestTrials { k, reply_tx };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
     pub fn set_study_direction(&self, direction: StudyDirection) {
         let message = Message::SetStudyDirection {
             direction,
             timestamp: Timestamp::now(),
+            author: self.author(),
         };
         let command = Command::Broadcast { message };
         let _ = self.command_tx.send(command);
@@ -324,6 +1275,7 @@ impl StudyNodeHandle {
             key,
             value,
             timestamp: Timestamp::now(),
+            author: self.author(),
         };
         let command = Command::Broadcast { message };
         let _ = self.command_tx.send(command);
@@ -334,39 +1286,174 @@ impl StudyNodeHandle {
             key,
             value,
             timestamp: Timestamp::now(),
+            author: self.author(),
         };
         let command = Command::Broadcast { message };
         let _ = self.command_tx.send(command);
     }
 
-    pub fn create_trial(&self, trial_id: TrialId) {
-        let message = Message::CreateTrial {
+    pub fn set_study_sampler(&self, sampler: Sampler) {
+        let message = Message::SetStudySampler {
+            sampler,
+            timestamp: Timestamp::now(),
+            author: self.author(),
+        };
+        let command = Command::Broadcast { message };
+        let _ = self.command_tx.send(command);
+    }
+
+    pub fn get_search_space(
+        &self,
+        mode: SearchSpaceMode,
+    ) -> impl Future<Item = SearchSpace, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::GetSearchSpace { mode, reply_tx };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    pub fn set_study_search_space(&self, search_space: SearchSpace) {
+        let message = Message::SetStudySearchSpace {
+            search_space,
+            timestamp: Timestamp::now(),
+            author: self.author(),
+        };
+        let command = Command::Broadcast { message };
+        let _ = self.command_tx.send(command);
+    }
+
+    pub fn set_study_tag(&self, key: String, value: Option<String>) {
+        let message = Message::SetStudyTag {
+            key,
+            value,
+            timestamp: Timestamp::now(),
+            author: self.author(),
+        };
+        let command = Command::Broadcast { message };
+        let _ = self.command_tx.send(command);
+    }
+
+    pub fn set_study_pruner(&self, pruner: Pruner) {
+        let message = Message::SetStudyPruner {
+            pruner,
+            timestamp: Timestamp::now(),
+            author: self.author(),
+        };
+        let command = Command::Broadcast { message };
+        let _ = self.command_tx.send(command);
+    }
+
+    pub fn should_prune(&self, trial_id: TrialId) -> impl Future<Item = bool, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::ShouldPrune {
+            trial_id,
+            author: self.author(),
+            reply_tx,
+        };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    pub fn sample_params(
+        &self,
+        trial_id: TrialId,
+        search_space: BTreeMap<String, Distribution>,
+    ) -> impl Future<Item = HashMap<String, TrialParamValue>, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::SampleParams {
+            trial_id,
+            search_space,
+            author: self.author(),
+            reply_tx,
+        };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    /// Creates a new trial.
+    ///
+    /// Fails with `ErrorKind::LimitExceeded` if the trial budget of this study is exhausted.
+    pub fn create_trial(&self, trial_id: TrialId) -> impl Future<Item = (), Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::CreateTrial {
             trial_id,
+            author: self.author(),
+            reply_tx,
+        };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    pub fn set_study_trial_budget(&self, budget: Option<TrialBudget>) {
+        let message = Message::SetStudyTrialBudget {
+            budget,
+            timestamp: Timestamp::now(),
+            author: self.author(),
+        };
+        let command = Command::Broadcast { message };
+        let _ = self.command_tx.send(command);
+    }
+
+    pub fn get_webhooks(&self) -> impl Future<Item = BTreeMap<String, Webhook>, Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::GetWebhooks { reply_tx };
+        let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
+    }
+
+    pub fn set_study_webhook(&self, name: String, webhook: Option<Webhook>) {
+        let message = Message::SetStudyWebhook {
+            name,
+            webhook,
+            timestamp: Timestamp::now(),
+            author: self.author(),
+        };
+        let command = Command::Broadcast { message };
+        let _ = self.command_tx.send(command);
+    }
+
+    pub fn set_study_lifetime(&self, lifetime: Option<StudyLifetime>) {
+        let message = Message::SetStudyLifetime {
+            lifetime,
             timestamp: Timestamp::now(),
+            author: self.author(),
         };
         let command = Command::Broadcast { message };
         let _ = self.command_tx.send(command);
     }
 
+    /// Extends the lifetime of the study on this node as if a write request has just been made.
+    pub fn keep_alive(&self) {
+        let _ = self.command_tx.send(Command::KeepAlive);
+    }
+
     pub fn set_trial_state(&self, trial_id: TrialId, state: TrialState) {
         let message = Message::SetTrialState {
             trial_id,
             state,
             timestamp: Timestamp::now(),
+            author: self.author(),
         };
         let command = Command::Broadcast { message };
         let _ = self.command_tx.send(command);
     }
 
-    pub fn set_trial_param(&self, trial_id: TrialId, key: String, value: TrialParamValue) {
-        let message = Message::SetTrialParam {
+    pub fn set_trial_param(
+        &self,
+        trial_id: TrialId,
+        key: String,
+        value: TrialParamValue,
+    ) -> impl Future<Item = (), Error = Error> {
+        let (reply_tx, reply_rx) = oneshot::monitor();
+        let command = Command::SetTrialParam {
             trial_id,
             key,
             value,
-            timestamp: Timestamp::now(),
+            author: self.author(),
+            reply_tx,
         };
-        let command = Command::Broadcast { message };
         let _ = self.command_tx.send(command);
+        track_err!(reply_rx.map_err(Error::from))
     }
 
     pub fn set_trial_value(&self, trial_id: TrialId, value: f64) {
@@ -374,6 +1461,18 @@ impl StudyNodeHandle {
             trial_id,
             value,
             timestamp: Timestamp::now(),
+            author: self.author(),
+        };
+        let command = Command::Broadcast { message };
+        let _ = self.command_tx.send(command);
+    }
+
+    pub fn set_trial_constraints(&self, trial_id: TrialId, constraints: Vec<f64>) {
+        let message = Message::SetTrialConstraints {
+            trial_id,
+            constraints,
+            timestamp: Timestamp::now(),
+            author: self.author(),
         };
         let command = Command::Broadcast { message };
         let _ = self.command_tx.send(command);
@@ -385,6 +1484,7 @@ impl StudyNodeHandle {
             step,
             value,
             timestamp: Timestamp::now(),
+            author: self.author(),
         };
         let command = Command::Broadcast { message };
         let _ = self.command_tx.send(command);
@@ -396,6 +1496,7 @@ impl StudyNodeHandle {
             key,
             value,
             timestamp: Timestamp::now(),
+            author: self.author(),
         };
         let command = Command::Broadcast { message };
         let _ = self.command_tx.send(command);
@@ -407,6 +1508,7 @@ impl StudyNodeHandle {
             key,
             value,
             timestamp: Timestamp::now(),
+            author: self.author(),
         };
         let command = Command::Broadcast { message };
         let _ = self.command_tx.send(command);
@@ -447,6 +1549,67 @@ enum Command {
     GetTrials {
         reply_tx: oneshot::Monitored<Vec<Trial>, Error>,
     },
+    CloneMessages {
+        study_id: StudyId,
+        author: Option<Author>,
+        reply_tx: oneshot::Monitored<Vec<Message>, Error>,
+    },
+    SelectTrials {
+        filter: TrialFilter,
+        reply_tx: oneshot::Monitored<Vec<Trial>, Error>,
+    },
+    ImportTrials {
+        trials: Vec<Trial>,
+        author: Option<Author>,
+        reply_tx: oneshot::Monitored<Vec<TrialId>, Error>,
+    },
+    GetStats {
+        reply_tx: oneshot::Monitored<StudyStats, Error>,
+    },
+    GetLearningCurves {
+        states: Option<Vec<TrialState>>,
+        percentiles: Vec<f64>,
+        reply_tx: oneshot::Monitored<LearningCurves, Error>,
+    },
+    GetWebhooks {
+        reply_tx: oneshot::Monitored<BTreeMap<String, Webhook>, Error>,
+    },
+    GetAuditLog {
+        trial_id: Option<TrialId>,
+        limit: usize,
+        reply_tx: oneshot::Monitored<Vec<Message>, Error>,
+    },
+    GetBestTrials {
+        k: usize,
+        reply_tx: oneshot::Monitored<Vec<Trial>, Error>,
+    },
+    SampleParams {
+        trial_id: TrialId,
+        search_space: BTreeMap<String, Distribution>,
+        author: Option<Author>,
+        reply_tx: oneshot::Monitored<HashMap<String, TrialParamValue>, Error>,
+    },
+    GetSearchSpace {
+        mode: SearchSpaceMode,
+        reply_tx: oneshot::Monitored<SearchSpace, Error>,
+    },
+    SetTrialParam {
+        trial_id: TrialId,
+        key: String,
+        value: TrialParamValue,
+        author: Option<Author>,
+        reply_tx: oneshot::Monitored<(), Error>,
+    },
+    CreateTrial {
+        trial_id: TrialId,
+        author: Option<Author>,
+        reply_tx: oneshot::Monitored<(), Error>,
+    },
+    ShouldPrune {
+        trial_id: TrialId,
+        author: Option<Author>,
+        reply_tx: oneshot::Monitored<bool, Error>,
+    },
     Subscribe {
         reply_tx: oneshot::Monitored<SubscribeId, Error>,
     },
@@ -457,4 +1620,132 @@ enum Command {
     Broadcast {
         message: Message,
     },
+    KeepAlive,
+}
+impl Command {
+    /// Returns `true` if the command is a write or an explicit keepalive.
+    ///
+    /// Reads do not extend the lifetime so that clients polling studies (e.g., dashboards) do not keep them forever.
+    fn extends_lifetime(&self) -> bool {
+        match self {
+            Command::GetSummary { .. }
+            | Command::GetTrial { .. }
+            | Command::GetTrials { .. }
+            | Command::CloneMessages { .. }
+            | Command::SelectTrials { .. }
+            | Command::GetStats { .. }
+            | Command::GetLearningCurves { .. }
+            | Command::GetWebhooks { .. }
+            | Command::GetAuditLog { .. }
+            | Command::GetBestTrials { .. }
+            | Command::GetSearchSpace { .. }
+            | Command::Subscribe { .. }
+            | Command::PollEvents { .. } => false,
+            Command::ImportTrials { .. }
+            | Command::SampleParams { .. }
+            | Command::SetTrialParam { .. }
+            | Command::CreateTrial { .. }
+            | Command::ShouldPrune { .. }
+            | Command::Broadcast { .. }
+            | Command::KeepAlive => true,
+        }
+    }
+}
+
+/// Makes the messages that create a completed trial `trial_id` with the same contents as `trial`.
+///
+/// The identifier of `trial` is recorded in the system attribute `origin_attr_key` of the new trial.
+///
+/// The start and end times of `trial` are kept, and the other messages are stamped with `timestamp`.
+///
+/// The system attributes reserved by plumtuna (`plumtuna:*`) and the bookkeeping ones of the pruners are not copied
+/// since they are meaningless in (and would mislead the samplers and pruners of) the target study.
+fn copy_trial_messages(
+    trial: &Trial,
+    trial_id: TrialId,
+    origin_attr_key: &str,
+    timestamp: Timestamp,
+    author: Option<Author>,
+) -> Vec<Message> {
+    let mut messages = vec![Message::CreateTrial {
+        trial_id: trial_id.clone(),
+        timestamp: trial
+            .datetime_start
+            .map_or(timestamp, Timestamp::from_seconds),
+        author: author.clone(),
+    }];
+    for (key, value) in &trial.params {
+        messages.push(Message::SetTrialParam {
+            trial_id: trial_id.clone(),
+            key: key.clone(),
+            value: TrialParamValue::new(value.value, value.distribution.clone()),
+            timestamp,
+            author: author.clone(),
+        });
+    }
+    for (&step, &value) in &trial.intermediate_values {
+        messages.push(Message::SetTrialIntermediateValue {
+            trial_id: trial_id.clone(),
+            step,
+            value,
+            timestamp,
+            author: author.clone(),
+        });
+    }
+    if let Some(constraints) = trial.constraints.clone() {
+        messages.push(Message::SetTrialConstraints {
+            trial_id: trial_id.clone(),
+            constraints,
+            timestamp,
+            author: author.clone(),
+        });
+    }
+    for (key, value) in &trial.user_attrs {
+        messages.push(Message::SetTrialUserAttr {
+            trial_id: trial_id.clone(),
+            key: key.clone(),
+            value: value.clone(),
+            timestamp,
+            author: author.clone(),
+        });
+    }
+    for (key, value) in trial
+        .system_attrs
+        .iter()
+        .filter(|(key, _)| !key.starts_with("plumtuna:") && !pruner::is_bookkeeping_attr_key(key))
+    {
+        messages.push(Message::SetTrialSystemAttr {
+            trial_id: trial_id.clone(),
+            key: key.clone(),
+            value: value.clone(),
+            timestamp,
+            author: author.clone(),
+        });
+    }
+    messages.push(Message::SetTrialSystemAttr {
+        trial_id: trial_id.clone(),
+        key: origin_attr_key.to_owned(),
+        value: trial.trial_id.as_str().into(),
+        timestamp,
+        author: author.clone(),
+    });
+    messages.push(Message::SetTrialValue {
+        trial_id: trial_id.clone(),
+        value: trial.value.expect("never fails"),
+        timestamp,
+        author: author.clone(),
+    });
+    messages.push(Message::SetTrialState {
+        trial_id,
+        state: TrialState::Complete,
+        timestamp: trial
+            .datetime_end
+            .map_or(timestamp, Timestamp::from_seconds),
+        author: author.clone(),
+    });
+    messages
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> JsonValue {
+    serde_json::to_value(value).expect("never fails")
 }
@@ -8,11 +8,21 @@ pub enum OperationKey {
     SetStudyDirection,
     SetStudyUserAttr { key: String },
     SetStudySystemAttr { key: String },
+    SetStudySampler,
+    SetStudyPruner,
+    SetStudySearchSpace,
+    SetStudyTag { key: String },
+    SetStudyAcl { principal: String },
+    SetStudyTrialBudget,
+    SetStudyLifetime,
+    SetStudyState,
+    SetStudyWebhook { name: String },
     CreateTrial { trial_id: TrialId }, // TODO: remove?
     SetTrialState { trial_id: TrialId },
     SetTrialParam { trial_id: TrialId, key: String },
     SetTrialValue { trial_id: TrialId },
     SetTrialIntermediateValue { trial_id: TrialId, step: u32 },
+    SetTrialConstraints { trial_id: TrialId },
     SetTrialUserAttr { trial_id: TrialId, key: String },
     SetTrialSystemAttr { trial_id: TrialId, key: String },
 }
@@ -26,6 +36,19 @@ impl OperationKey {
             Message::SetStudySystemAttr { key, .. } => {
                 OperationKey::SetStudySystemAttr { key: key.clone() }
             }
+            Message::SetStudySampler { .. } => OperationKey::SetStudySampler,
+            Message::SetStudyPruner { .. } => OperationKey::SetStudyPruner,
+            Message::SetStudySearchSpace { .. } => OperationKey::SetStudySearchSpace,
+            Message::SetStudyTag { key, .. } => OperationKey::SetStudyTag { key: key.clone() },
+            Message::SetStudyAcl { principal, .. } => OperationKey::SetStudyAcl {
+                principal: principal.clone(),
+            },
+            Message::SetStudyTrialBudget { .. } => OperationKey::SetStudyTrialBudget,
+            Message::SetStudyLifetime { .. } => OperationKey::SetStudyLifetime,
+            Message::SetStudyState { .. } => OperationKey::SetStudyState,
+            Message::SetStudyWebhook { name, .. } => {
+                OperationKey::SetStudyWebhook { name: name.clone() }
+            }
             Message::CreateTrial { trial_id, .. } => OperationKey::CreateTrial {
                 trial_id: trial_id.clone(),
             },
@@ -45,6 +68,9 @@ impl OperationKey {
                     step: *step,
                 }
             }
+            Message::SetTrialConstraints { trial_id, .. } => OperationKey::SetTrialConstraints {
+                trial_id: trial_id.clone(),
+            },
             Message::SetTrialUserAttr { trial_id, key, .. } => OperationKey::SetTrialUserAttr {
                 trial_id: trial_id.clone(),
                 key: key.clone(),
@@ -0,0 +1,233 @@
+use crate::pruner::percentile_of;
+use crate::time::Seconds;
+use crate::trial::{Trial, TrialId, TrialState};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StudyStats {
+    pub n_trials: HashMap<TrialState, u32>,
+    pub value_quantiles: Option<Quantiles>,
+    pub duration: Option<Quantiles>,
+    pub completed_trials_per_minute: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quantiles {
+    pub min: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+impl Quantiles {
+    fn new(mut values: Vec<f64>) -> Option<Self> {
+        let median = percentile_of(&mut values, 50.0)?;
+        Some(Quantiles {
+            min: values[0],
+            p25: percentile_of(&mut values, 25.0)?,
+            median,
+            p75: percentile_of(&mut values, 75.0)?,
+            max: values[values.len() - 1],
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+        })
+    }
+}
+
+/// Incrementally maintained counters for [`StudyStats`].
+#[derive(Debug, Default)]
+pub struct StatsCounter {
+    n_trials: HashMap<TrialState, u32>,
+    values: HashMap<TrialId, f64>,
+    durations: HashMap<TrialId, f64>,
+    first_start: Option<f64>,
+}
+impl StatsCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the counters by the difference between `before` and `after` of a trial.
+    pub fn update(&mut self, before: Option<TrialSnapshot>, after: Option<TrialSnapshot>) {
+        if let Some(s) = before {
+            *self.n_trials.entry(s.state).or_default() -= 1;
+        }
+        if let Some(s) = after {
+            *self.n_trials.entry(s.state).or_default() += 1;
+            self.first_start = Some(self.first_start.map_or(s.start, |t| t.min(s.start)));
+            let trial_id = s.trial_id;
+            match s.value {
+                Some(v) if s.state == TrialState::Complete && !v.is_nan() => {
+                    self.values.insert(trial_id.clone(), v);
+                }
+                _ => {
+                    self.values.remove(&trial_id);
+                }
+            }
+            if let Some(d) = s.duration {
+                self.durations.insert(trial_id, d);
+            } else {
+                self.durations.remove(&trial_id);
+            }
+        }
+    }
+
+    pub fn stats(&self, now: Seconds) -> StudyStats {
+        let n_trials = self
+            .n_trials
+            .iter()
+            .filter(|x| *x.1 > 0)
+            .map(|(k, v)| (*k, *v))
+            .collect::<HashMap<_, _>>();
+        let n_completed = n_trials.get(&TrialState::Complete).cloned().unwrap_or(0);
+        let completed_trials_per_minute = self.first_start.map_or(0.0, |start| {
+            let minutes = (now.as_f64() - start) / 60.0;
+            if minutes > 0.0 {
+                f64::from(n_completed) / minutes
+            } else {
+                0.0
+            }
+        });
+        StudyStats {
+            n_trials,
+            value_quantiles: Quantiles::new(self.values.values().cloned().collect()),
+            duration: Quantiles::new(self.durations.values().cloned().collect()),
+            completed_trials_per_minute,
+        }
+    }
+}
+
+/// The part of a trial that affects [`StatsCounter`].
+#[derive(Debug)]
+pub struct TrialSnapshot {
+    trial_id: TrialId,
+    state: TrialState,
+    value: Option<f64>,
+    start: f64,
+    duration: Option<f64>,
+}
+impl TrialSnapshot {
+    pub fn new(trial: &Trial) -> Option<Self> {
+        let state = trial.visible_state()?;
+        let start = trial.datetime_start?.as_f64();
+        let duration = if state == TrialState::Running {
+            None
+        } else {
+            trial.datetime_end.map(|end| end.as_f64() - start)
+        };
+        Some(TrialSnapshot {
+            trial_id: trial.trial_id.clone(),
+            state,
+            value: trial.value,
+            start,
+            duration,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::study::StudyId;
+    use crate::time::Timestamp;
+
+    fn seconds(s: f64) -> Seconds {
+        serde_json::from_value(s.into()).unwrap()
+    }
+
+    fn trial(start: f64, end: Option<f64>, state: TrialState, value: Option<f64>) -> Trial {
+        let mut trial = Trial::new(TrialId::new(&StudyId::new()));
+        trial.value = value;
+        trial.set_state(state, Timestamp::now());
+        trial.datetime_start = Some(seconds(start));
+        trial.datetime_end = end.map(seconds);
+        trial
+    }
+
+    fn add(counter: &mut StatsCounter, trial: &Trial) {
+        counter.update(None, TrialSnapshot::new(trial));
+    }
+
+    #[test]
+    fn stats_work() {
+        let mut counter = StatsCounter::new();
+        for i in 1..=4 {
+            let start = f64::from(i);
+            let value = Some(f64::from(i));
+            let end = Some(start + 10.0 * f64::from(i));
+            add(
+                &mut counter,
+                &trial(start, end, TrialState::Complete, value),
+            );
+        }
+        add(&mut counter, &trial(0.0, None, TrialState::Running, None));
+        add(
+            &mut counter,
+            &trial(5.0, Some(55.0), TrialState::Pruned, None),
+        );
+        add(
+            &mut counter,
+            &trial(5.0, Some(5.0), TrialState::Complete, Some(f64::NAN)),
+        );
+
+        let stats = counter.stats(seconds(120.0));
+        assert_eq!(stats.n_trials[&TrialState::Complete], 5);
+        assert_eq!(stats.n_trials[&TrialState::Running], 1);
+        assert_eq!(stats.n_trials[&TrialState::Pruned], 1);
+        assert_eq!(stats.completed_trials_per_minute, 2.5);
+
+        // NaN values are excluded.
+        let values = stats.value_quantiles.unwrap();
+        assert_eq!(values.min, 1.0);
+        assert_eq!(values.p25, 1.75);
+        assert_eq!(values.median, 2.5);
+        assert_eq!(values.p75, 3.25);
+        assert_eq!(values.max, 4.0);
+        assert_eq!(values.mean, 2.5);
+
+        // The durations of the finished trials (including the pruned one) are aggregated.
+        let duration = stats.duration.unwrap();
+        assert_eq!(duration.min, 0.0);
+        assert_eq!(duration.median, 25.0);
+        assert_eq!(duration.max, 50.0);
+    }
+
+    #[test]
+    fn updated_trials_are_not_counted_twice() {
+        let mut counter = StatsCounter::new();
+        let running = trial(0.0, None, TrialState::Running, None);
+        add(&mut counter, &running);
+
+        let mut completed = running.clone();
+        completed.value = Some(3.0);
+        completed.set_state(TrialState::Complete, Timestamp::now());
+        completed.datetime_end = Some(seconds(30.0));
+        counter.update(TrialSnapshot::new(&running), TrialSnapshot::new(&completed));
+
+        let stats = counter.stats(seconds(60.0));
+        assert_eq!(stats.n_trials.len(), 1);
+        assert_eq!(stats.n_trials[&TrialState::Complete], 1);
+        assert_eq!(stats.value_quantiles.unwrap().mean, 3.0);
+        assert_eq!(stats.duration.unwrap().mean, 30.0);
+        assert_eq!(stats.completed_trials_per_minute, 1.0);
+    }
+
+    #[test]
+    fn empty_stats() {
+        let counter = StatsCounter::new();
+        let stats = counter.stats(seconds(60.0));
+        assert!(stats.n_trials.is_empty());
+        assert!(stats.value_quantiles.is_none());
+        assert!(stats.duration.is_none());
+        assert_eq!(stats.completed_trials_per_minute, 0.0);
+
+        // Completed trials without values are regarded as running, and undelivered trials are ignored.
+        let mut trial = trial(0.0, Some(1.0), TrialState::Complete, None);
+        assert_eq!(
+            TrialSnapshot::new(&trial).unwrap().state,
+            TrialState::Running
+        );
+        trial.datetime_start = None;
+        assert!(TrialSnapshot::new(&trial).is_none());
+    }
+}
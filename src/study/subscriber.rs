@@ -9,12 +9,18 @@ impl SubscribeId {
         SubscribeId(0)
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> SubscribeId {
         let x = SubscribeId(self.0);
         self.0 += 1;
         x
     }
 }
+impl Default for SubscribeId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl From<u32> for SubscribeId {
     fn from(f: u32) -> Self {
         Self(f)
@@ -39,7 +45,7 @@ impl Subscriber {
     }
 
     pub fn pop_messages(&mut self) -> Vec<Message> {
-        mem::replace(&mut self.messages, Vec::new())
+        mem::take(&mut self.messages)
     }
 
     pub fn heartbeat(&mut self, now: Duration) {
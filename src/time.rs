@@ -7,6 +7,10 @@ impl Timestamp {
         Self(UNIX_EPOCH.elapsed().expect("never fails"))
     }
 
+    pub fn from_seconds(seconds: Seconds) -> Self {
+        Self(Duration::from_secs_f64(seconds.0.max(0.0)))
+    }
+
     pub fn to_seconds(&self) -> Seconds {
         let d = self.0;
         let s = (d.as_secs() as f64) + ((d.subsec_micros() as f64) / 1_000_000.0);
@@ -22,4 +26,8 @@ impl Seconds {
         let s = (d.as_secs() as f64) + ((d.subsec_micros() as f64) / 1_000_000.0);
         Self(s)
     }
+
+    pub fn as_f64(self) -> f64 {
+        self.0
+    }
 }
@@ -0,0 +1,406 @@
+//! HTTPS support for the HTTP API.
+//!
+//! `fibers_http_server` only speaks plain HTTP, so TLS connections are accepted by [`TlsTerminator`]
+//! and relayed to the HTTP server listening on a loopback address.
+use crate::{Error, ErrorKind, Result};
+use rustls::crypto;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig, ServerConnection};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use trackable::error::ErrorKindExt;
+
+const BUFFER_SIZE: usize = 16 * 1024;
+
+/// PEM files used to serve HTTPS.
+#[derive(Debug, Clone)]
+pub struct TlsOptions {
+    /// The certificate chain of the server.
+    pub cert: PathBuf,
+
+    /// The private key of the server.
+    pub key: PathBuf,
+
+    /// The CA certificates used to verify client certificates.
+    ///
+    /// If given, clients that do not present a certificate signed by one of them are rejected.
+    pub client_ca: Option<PathBuf>,
+}
+impl TlsOptions {
+    pub fn server_config(&self) -> Result<Arc<ServerConfig>> {
+        let provider = Arc::new(crypto::ring::default_provider());
+        let certs = track!(load_certs(&self.cert))?;
+        let key = track!(PrivateKeyDer::from_pem_file(&self.key).map_err(invalid_input); self.key)?;
+
+        let builder = track!(ServerConfig::builder_with_provider(Arc::clone(&provider))
+            .with_safe_default_protocol_versions()
+            .map_err(invalid_input))?;
+        let builder = if let Some(path) = &self.client_ca {
+            let mut roots = RootCertStore::empty();
+            for cert in track!(load_certs(path))? {
+                track!(roots.add(cert).map_err(invalid_input); path)?;
+            }
+            let verifier = track!(WebPkiClientVerifier::builder_with_provider(
+                Arc::new(roots),
+                Arc::clone(&provider)
+            )
+            .build()
+            .map_err(invalid_input); path)?;
+            builder.with_client_cert_verifier(verifier)
+        } else {
+            builder.with_no_client_auth()
+        };
+        let config = track!(builder.with_single_cert(certs, key).map_err(invalid_input))?;
+        Ok(Arc::new(config))
+    }
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = track!(CertificateDer::pem_file_iter(path).map_err(invalid_input); path)?
+        .collect::<std::result::Result<Vec<_>, _>>();
+    let certs = track!(certs.map_err(invalid_input); path)?;
+    track_assert!(
+        !certs.is_empty(),
+        ErrorKind::InvalidInput,
+        "No certificates: {:?}",
+        path
+    );
+    Ok(certs)
+}
+
+fn invalid_input<E>(e: E) -> Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    ErrorKind::InvalidInput.cause(e).into()
+}
+
+/// A listener that terminates TLS connections and relays the decrypted bytes to `backend`.
+///
+/// Each connection is handled by its own pair of threads.
+#[derive(Debug)]
+pub struct TlsTerminator {
+    listener: TcpListener,
+    config: Arc<ServerConfig>,
+    backend: SocketAddr,
+}
+impl TlsTerminator {
+    pub fn bind(addr: SocketAddr, config: Arc<ServerConfig>, backend: SocketAddr) -> Result<Self> {
+        let listener = track!(TcpListener::bind(addr)
+            .map_err(|e| Error::from(ErrorKind::Other.cause(e))); addr)?;
+        Ok(TlsTerminator {
+            listener,
+            config,
+            backend,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        track!(self
+            .listener
+            .local_addr()
+            .map_err(|e| Error::from(ErrorKind::Other.cause(e))))
+    }
+
+    /// Spawns the thread that accepts connections.
+    pub fn spawn(self) {
+        thread::spawn(move || {
+            for client in self.listener.incoming() {
+                let client = match client {
+                    Ok(client) => client,
+                    Err(e) => {
+                        log::warn!("Cannot accept a TLS connection: {}", e);
+                        continue;
+                    }
+                };
+                let config = Arc::clone(&self.config);
+                let backend = self.backend;
+                thread::spawn(move || {
+                    if let Err(e) = relay(client, config, backend) {
+                        log::debug!("TLS connection closed: {}", e);
+                    }
+                });
+            }
+        });
+    }
+}
+
+/// The TLS state of a connection and the client socket to which its records are written.
+#[derive(Debug)]
+struct Session {
+    conn: Mutex<ServerConnection>,
+    client: Mutex<TcpStream>,
+}
+impl Session {
+    /// Applies `f` to the connection and writes the resulting TLS records to the client.
+    ///
+    /// The connection is unlocked while writing the records so that a client that stops reading
+    /// does not block the decryption of the bytes it sends (unless the decryption yields records to be sent).
+    /// The client is locked before unlocking the connection to keep the order of the records.
+    fn with_conn<F, T>(&self, f: F) -> io::Result<T>
+    where
+        F: FnOnce(&mut ServerConnection) -> io::Result<T>,
+    {
+        let mut conn = self.conn.lock().expect("never fails");
+        let result = f(&mut conn);
+        if conn.wants_write() {
+            let mut records = Vec::new();
+            while conn.wants_write() {
+                conn.write_tls(&mut records)?;
+            }
+            let mut client = self.client.lock().expect("never fails");
+            drop(conn);
+            client.write_all(&records)?;
+        }
+        result
+    }
+}
+
+fn relay(client: TcpStream, config: Arc<ServerConfig>, backend: SocketAddr) -> io::Result<()> {
+    let conn = ServerConnection::new(config).map_err(io::Error::other)?;
+    let backend = TcpStream::connect(backend)?;
+    let session = Arc::new(Session {
+        conn: Mutex::new(conn),
+        client: Mutex::new(client.try_clone()?),
+    });
+
+    let backend_reader = backend.try_clone()?;
+    let session_for_writer = Arc::clone(&session);
+    let writer = thread::spawn(move || relay_to_client(backend_reader, &session_for_writer));
+
+    let result = relay_from_client(client, &backend, &session);
+    if result.is_err() {
+        let _ = backend.shutdown(Shutdown::Both);
+    }
+    let _ = writer.join();
+    result
+}
+
+/// Decrypts the bytes sent by the client and forwards them to the backend.
+fn relay_from_client(
+    mut client: TcpStream,
+    mut backend: &TcpStream,
+    session: &Session,
+) -> io::Result<()> {
+    let mut buf = vec![0; BUFFER_SIZE];
+    let mut plaintext = Vec::new();
+    loop {
+        let size = client.read(&mut buf)?;
+        if size == 0 {
+            backend.shutdown(Shutdown::Write)?;
+            return Ok(());
+        }
+        session.with_conn(|conn| {
+            let mut received = &buf[..size];
+            while !received.is_empty() {
+                conn.read_tls(&mut received)?;
+                // If this fails, the alert queued by it is sent to the client by `with_conn`.
+                conn.process_new_packets()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                match conn.reader().read_to_end(&mut plaintext) {
+                    Ok(_) => {}
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        })?;
+        backend.write_all(&plaintext)?;
+        plaintext.clear();
+    }
+}
+
+/// Encrypts the bytes sent by the backend and forwards them to the client.
+fn relay_to_client(mut backend: TcpStream, session: &Session) -> io::Result<()> {
+    let mut buf = vec![0; BUFFER_SIZE];
+    loop {
+        let size = backend.read(&mut buf)?;
+        if size == 0 {
+            session.with_conn(|conn| {
+                conn.send_close_notify();
+                Ok(())
+            })?;
+            let client = session.client.lock().expect("never fails");
+            return client.shutdown(Shutdown::Write);
+        }
+        session.with_conn(|conn| conn.writer().write_all(&buf[..size]))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, Issuer, KeyPair};
+    use rustls::pki_types::ServerName;
+    use rustls::{ClientConfig, ClientConnection, StreamOwned};
+    use std::convert::TryFrom;
+    use std::fs;
+
+    struct Pki {
+        dir: PathBuf,
+        ca: CertificateDer<'static>,
+        client_cert: CertificateDer<'static>,
+        client_key: PrivateKeyDer<'static>,
+    }
+    impl Pki {
+        /// Generates a CA and a server and a client certificates signed by it.
+        fn generate(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("plumtuna-tls-{}-{}", name, std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+
+            let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+            ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca_key = KeyPair::generate().unwrap();
+            let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+            let issuer = Issuer::new(ca_params, ca_key);
+
+            let server_key = KeyPair::generate().unwrap();
+            let server_cert = CertificateParams::new(vec!["localhost".to_owned()])
+                .unwrap()
+                .signed_by(&server_key, &issuer)
+                .unwrap();
+            let client_key = KeyPair::generate().unwrap();
+            let client_cert = CertificateParams::new(vec!["worker".to_owned()])
+                .unwrap()
+                .signed_by(&client_key, &issuer)
+                .unwrap();
+
+            fs::write(dir.join("ca.pem"), ca_cert.pem()).unwrap();
+            fs::write(dir.join("server.pem"), server_cert.pem()).unwrap();
+            fs::write(dir.join("server.key"), server_key.serialize_pem()).unwrap();
+            Pki {
+                dir,
+                ca: ca_cert.der().clone(),
+                client_cert: client_cert.der().clone(),
+                client_key: PrivateKeyDer::try_from(client_key.serialize_der()).unwrap(),
+            }
+        }
+
+        fn options(&self, require_client_cert: bool) -> TlsOptions {
+            TlsOptions {
+                cert: self.dir.join("server.pem"),
+                key: self.dir.join("server.key"),
+                client_ca: if require_client_cert {
+                    Some(self.dir.join("ca.pem"))
+                } else {
+                    None
+                },
+            }
+        }
+
+        fn client_config(&self, with_client_cert: bool) -> Arc<ClientConfig> {
+            let mut roots = RootCertStore::empty();
+            roots.add(self.ca.clone()).unwrap();
+            let builder =
+                ClientConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                    .with_safe_default_protocol_versions()
+                    .unwrap()
+                    .with_root_certificates(roots);
+            let config = if with_client_cert {
+                builder
+                    .with_client_auth_cert(
+                        vec![self.client_cert.clone()],
+                        self.client_key.clone_key(),
+                    )
+                    .unwrap()
+            } else {
+                builder.with_no_client_auth()
+            };
+            Arc::new(config)
+        }
+    }
+    impl Drop for Pki {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    /// Starts a backend that answers every connection with a fixed HTTP response.
+    fn start_backend() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+            }
+        });
+        addr
+    }
+
+    fn start_terminator(options: &TlsOptions) -> SocketAddr {
+        let config = options.server_config().unwrap();
+        let terminator =
+            TlsTerminator::bind(([127, 0, 0, 1], 0).into(), config, start_backend()).unwrap();
+        let addr = terminator.local_addr().unwrap();
+        terminator.spawn();
+        addr
+    }
+
+    fn get(addr: SocketAddr, config: Arc<ClientConfig>) -> io::Result<String> {
+        let conn =
+            ClientConnection::new(config, ServerName::try_from("localhost").unwrap()).unwrap();
+        let mut stream = StreamOwned::new(conn, TcpStream::connect(addr)?);
+        stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    }
+
+    #[test]
+    fn serves_https() {
+        let pki = Pki::generate("serves_https");
+        let addr = start_terminator(&pki.options(false));
+        let response = get(addr, pki.client_config(false)).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.ends_with("hello"), "{}", response);
+    }
+
+    #[test]
+    fn rejects_untrusted_server_certificate() {
+        let pki = Pki::generate("untrusted_server");
+        let other = Pki::generate("untrusted_server_other");
+        let addr = start_terminator(&pki.options(false));
+        assert!(get(addr, other.client_config(false)).is_err());
+    }
+
+    #[test]
+    fn requires_client_certificate() {
+        let pki = Pki::generate("client_cert");
+        let addr = start_terminator(&pki.options(true));
+
+        let response = get(addr, pki.client_config(false));
+        assert!(
+            response.as_ref().map_or(true, |r| r.is_empty()),
+            "{:?}",
+            response
+        );
+
+        let response = get(addr, pki.client_config(true)).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    }
+
+    #[test]
+    fn rejects_missing_files() {
+        let options = TlsOptions {
+            cert: PathBuf::from("/nonexistent/server.pem"),
+            key: PathBuf::from("/nonexistent/server.key"),
+            client_ca: None,
+        };
+        assert!(options.server_config().is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::distribution::Distribution;
+use crate::distribution::{Distribution, DistributionFormat};
 use crate::time::{Seconds, Timestamp};
 use crate::{Error, ErrorKind, Result};
 use serde_json::Value as JsonValue;
@@ -13,6 +13,10 @@ impl TrialId {
         TrialId(format!("{}.{}", study_id.as_uuid(), Uuid::new_v4()))
     }
 
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
     pub fn get_study_id(&self) -> Result<crate::study::StudyId> {
         let s = track_assert_some!(self.0.split('.').nth(0), ErrorKind::Other);
         let uuid: Uuid = track!(s.parse().map_err(Error::from))?;
@@ -34,6 +38,7 @@ pub struct Trial {
     pub params: HashMap<String, TrialParamValue>,
     pub user_attrs: HashMap<String, JsonValue>,
     pub system_attrs: HashMap<String, JsonValue>,
+    pub constraints: Option<Vec<f64>>,
     pub datetime_start: Option<Seconds>,
     pub datetime_end: Option<Seconds>,
 }
@@ -47,6 +52,7 @@ impl Trial {
             params: HashMap::new(),
             user_attrs: HashMap::new(),
             system_attrs: HashMap::new(),
+            constraints: None,
             datetime_start: None,
             datetime_end: None,
         }
@@ -56,6 +62,29 @@ impl Trial {
         self.state == TrialState::Complete
     }
 
+    /// Returns `true` unless some of the constraint values are positive.
+    ///
+    /// Trials that have not reported constraints are regarded as feasible.
+    pub fn is_feasible(&self) -> bool {
+        self.constraints
+            .as_ref()
+            .is_none_or(|c| c.iter().all(|&v| v <= 0.0))
+    }
+
+    /// Returns the state of the trial as seen from clients.
+    ///
+    /// Returns `None` if the creation of the trial has not been delivered yet.
+    /// A completed trial without any value is regarded as running.
+    pub fn visible_state(&self) -> Option<TrialState> {
+        if self.datetime_start.is_none() {
+            None
+        } else if self.state == TrialState::Complete && self.value.is_none() {
+            Some(TrialState::Running)
+        } else {
+            Some(self.state)
+        }
+    }
+
     pub fn set_state(&mut self, state: TrialState, timestamp: Timestamp) {
         self.state = state;
         if state != TrialState::Running {
@@ -63,17 +92,23 @@ impl Trial {
         }
     }
 
+    pub fn convert_distributions(&mut self, format: DistributionFormat) {
+        for param in self.params.values_mut() {
+            param.distribution = param.distribution.convert(format);
+        }
+    }
+
     pub fn adjust(&self) -> Option<Self> {
-        if self.datetime_start.is_none() {
-            None
-        } else {
-            let mut trial = self.clone();
-            if trial.state == TrialState::Complete && trial.value.is_none() {
-                trial.state = TrialState::Running;
-                trial.datetime_end = None;
-            }
-            Some(trial)
+        let state = self.visible_state()?;
+        let mut trial = self.clone();
+        for param in trial.params.values_mut() {
+            param.fill_external_value();
+        }
+        if state != trial.state {
+            trial.state = state;
+            trial.datetime_end = None;
         }
+        Some(trial)
     }
 }
 
@@ -81,6 +116,25 @@ impl Trial {
 pub struct TrialParamValue {
     pub value: f64,
     pub distribution: Distribution,
+
+    /// The external representation of `value` (e.g., the chosen category itself).
+    ///
+    /// This is only filled in API responses.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub external_value: Option<JsonValue>,
+}
+impl TrialParamValue {
+    pub fn new(value: f64, distribution: Distribution) -> Self {
+        TrialParamValue {
+            value,
+            distribution,
+            external_value: None,
+        }
+    }
+
+    pub fn fill_external_value(&mut self) {
+        self.external_value = Some(self.distribution.to_external_repr(self.value));
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -0,0 +1,96 @@
+use crate::search_space::SearchSpace;
+use crate::study::StudyDirection;
+use crate::trial::Trial;
+use crate::{ErrorKind, Result};
+use std::cmp::Ordering;
+
+/// The system attribute key that marks the trials imported from other studies.
+///
+/// The value is the identifier of the original trial.
+pub const WARM_START_ATTR_KEY: &str = "plumtuna:warm_start_origin";
+
+/// Conditions on the completed trials of a source study to be imported into a warm-started study.
+#[derive(Debug, Clone)]
+pub struct TrialFilter {
+    /// The direction of the warm-started study.
+    pub direction: StudyDirection,
+
+    /// Only trials that are better than or equal to this value are selected.
+    pub value_threshold: Option<f64>,
+
+    /// The maximum number of trials selected from each source study.
+    pub top_k: Option<usize>,
+
+    /// The search space of the warm-started study.
+    pub search_space: Option<SearchSpace>,
+
+    /// The minimum fraction of `search_space` that must be covered by the parameters of a selected trial.
+    pub min_param_overlap: Option<f64>,
+}
+impl TrialFilter {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(overlap) = self.min_param_overlap {
+            track_assert!(
+                (0.0..=1.0).contains(&overlap),
+                ErrorKind::InvalidInput,
+                "`min_param_overlap` must be in the range [0, 1]: {}",
+                overlap
+            );
+            track_assert!(
+                self.search_space.is_some(),
+                ErrorKind::InvalidInput,
+                "`min_param_overlap` requires the search space of the study to be declared"
+            );
+        }
+        Ok(())
+    }
+
+    /// Selects the trials that satisfy the conditions from `trials`, ordered from the best.
+    pub fn select(&self, trials: Vec<Trial>) -> Vec<Trial> {
+        let mut trials = trials
+            .into_iter()
+            .filter(|t| t.is_complete())
+            .filter(|t| t.value.is_some_and(|v| !v.is_nan()))
+            .filter(|t| {
+                self.value_threshold.is_none_or(|threshold| {
+                    self.compare(t.value, Some(threshold)) != Ordering::Greater
+                })
+            })
+            .filter(|t| {
+                self.min_param_overlap
+                    .is_none_or(|overlap| self.param_overlap(t) >= overlap)
+            })
+            .collect::<Vec<_>>();
+        trials.sort_by(|a, b| self.compare(a.value, b.value));
+        if let Some(k) = self.top_k {
+            trials.truncate(k);
+        }
+        trials
+    }
+
+    fn compare(&self, a: Option<f64>, b: Option<f64>) -> Ordering {
+        let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+        if self.direction == StudyDirection::Maximize {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    fn param_overlap(&self, trial: &Trial) -> f64 {
+        let search_space = match self.search_space {
+            Some(ref s) if !s.is_empty() => s,
+            _ => return 1.0,
+        };
+        let n = search_space
+            .iter()
+            .filter(|(key, distribution)| {
+                trial.params.get(*key).is_some_and(|p| {
+                    distribution.check_compatibility(&p.distribution).is_ok()
+                        && distribution.contains(p.value)
+                })
+            })
+            .count();
+        n as f64 / search_space.len() as f64
+    }
+}